@@ -1,5 +1,6 @@
 ﻿#![no_std]
 use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, String, Symbol, Map, Vec, Error};
+use shared::math;
 
 #[contract]
 pub struct AccountingSystem;
@@ -41,6 +42,37 @@ pub struct FinancialReport {
     pub cash_flow: i128,
 }
 
+// Mirrors payment_processor::SettlementReport so batches can be imported as-is
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettlementReport {
+    pub batch_id: u32,
+    pub merchant: Address,
+    pub token: Address,
+    pub period_start: u64,
+    pub period_end: u64,
+    pub payment_count: u32,
+    pub gross_amount: i128,
+    pub fee_amount: i128,
+    pub refund_amount: i128,
+    pub net_amount: i128,
+}
+
+// Transactions and imported batch markers each live under their own persistent key;
+// the count and running totals stay in instance storage
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Transaction(u32),
+    TransactionCount,
+    Imported(u32),
+    TotalRevenue,
+    TotalExpenses,
+}
+
+const BUMP_THRESHOLD: u32 = 17_280; // ~1 day
+const BUMP_AMOUNT: u32 = 518_400; // ~30 days
+
 #[contractimpl]
 impl AccountingSystem {
     pub fn initialize(env: Env, admin: Address) {
@@ -54,15 +86,78 @@ impl AccountingSystem {
     ) -> Result<(), Error> {
         admin.require_auth();
 
-        let transactions_key = Symbol::new(&env, "TRANSACTIONS");
-        let mut transactions: Vec<FinancialTransaction> = env
-            .storage()
-            .instance()
-            .get(&transactions_key)
-            .unwrap_or_else(|| Vec::new(&env));
+        Self::_append_transaction(&env, &transaction)
+    }
+
+    // The payment_processor allowed to push its own settlement reports
+    pub fn set_payment_processor(env: Env, admin: Address, payment_processor: Address) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "PAYMENT_PROCESSOR"), &payment_processor);
+        Ok(())
+    }
+
+    pub fn import_settlement_report(
+        env: Env,
+        caller: Address,
+        report: SettlementReport,
+    ) -> Result<(), Error> {
+        caller.require_auth();
+        if env.storage().instance().get::<Symbol, Address>(&Symbol::new(&env, "PAYMENT_PROCESSOR")) != Some(caller.clone()) {
+            Self::_require_admin(&env, &caller)?;
+        }
+
+        let net_amount = report
+            .gross_amount
+            .checked_sub(report.fee_amount)
+            .and_then(|amount| amount.checked_sub(report.refund_amount))
+            .ok_or(Error::from_contract_error(2004))?; // Amount overflow
+        if net_amount != report.net_amount {
+            return Err(Error::from_contract_error(2001)); // Report does not balance
+        }
+
+        let imported_key = DataKey::Imported(report.batch_id);
+        if env.storage().persistent().has(&imported_key) {
+            return Err(Error::from_contract_error(2002)); // Batch already imported
+        }
+
+        Self::_append_transaction(&env, &FinancialTransaction {
+            transaction_id: Self::_settlement_transaction_id(&env, "SETTLEMENT_GROSS_", report.batch_id),
+            from_account: String::from_str(&env, "payment_processor"),
+            to_account: String::from_str(&env, "sales_revenue"),
+            amount: report.gross_amount,
+            transaction_type: TransactionType::Revenue,
+            description: String::from_str(&env, "Merchant settlement gross sales"),
+            timestamp: report.period_end,
+        })?;
+
+        if report.fee_amount > 0 {
+            Self::_append_transaction(&env, &FinancialTransaction {
+                transaction_id: Self::_settlement_transaction_id(&env, "SETTLEMENT_FEES_", report.batch_id),
+                from_account: String::from_str(&env, "sales_revenue"),
+                to_account: String::from_str(&env, "processing_fees"),
+                amount: report.fee_amount,
+                transaction_type: TransactionType::Expense,
+                description: String::from_str(&env, "Merchant settlement processing fees"),
+                timestamp: report.period_end,
+            })?;
+        }
+
+        if report.refund_amount > 0 {
+            Self::_append_transaction(&env, &FinancialTransaction {
+                transaction_id: Self::_settlement_transaction_id(&env, "SETTLEMENT_REFUNDS_", report.batch_id),
+                from_account: String::from_str(&env, "sales_revenue"),
+                to_account: String::from_str(&env, "customers"),
+                amount: report.refund_amount,
+                transaction_type: TransactionType::Refund,
+                description: String::from_str(&env, "Merchant settlement refunds"),
+                timestamp: report.period_end,
+            })?;
+        }
 
-        transactions.push_back(transaction);
-        env.storage().instance().set(&transactions_key, &transactions);
+        env.storage().persistent().set(&imported_key, &true);
+        env.storage().persistent().extend_ttl(&imported_key, BUMP_THRESHOLD, BUMP_AMOUNT);
 
         Ok(())
     }
@@ -71,57 +166,47 @@ impl AccountingSystem {
         env: Env,
         period: String,
     ) -> Result<FinancialReport, Error> {
-        let transactions: Vec<FinancialTransaction> = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, "TRANSACTIONS"))
-            .unwrap_or_else(|| Vec::new(&env));
-
-        let mut total_revenue = 0;
-        let mut total_expenses = 0;
-
-        for i in 0..transactions.len() {
-            let transaction = transactions.get(i).unwrap();
-            match transaction.transaction_type {
-                TransactionType::Revenue => total_revenue += transaction.amount,
-                TransactionType::Expense | TransactionType::Tax | TransactionType::Payroll => {
-                    total_expenses += transaction.amount
-                },
-                _ => {}
-            }
-        }
+        let total_revenue: i128 = env.storage().instance().get(&DataKey::TotalRevenue).unwrap_or(0);
+        let total_expenses: i128 = env.storage().instance().get(&DataKey::TotalExpenses).unwrap_or(0);
 
-        let net_income = total_revenue - total_expenses;
+        let net_income = math::sub(total_revenue, total_expenses)?;
 
         let report = FinancialReport {
             period,
             total_revenue,
             total_expenses,
             net_income,
-            assets: total_revenue * 2, // Mock data
+            assets: math::mul(total_revenue, 2)?, // Mock data
             liabilities: total_expenses, // Mock data
-            equity: net_income * 3, // Mock data
-            cash_flow: net_income - total_expenses / 2, // Mock data
+            equity: math::mul(net_income, 3)?, // Mock data
+            cash_flow: math::sub(net_income, total_expenses / 2)?, // Mock data
         };
 
         Ok(report)
     }
 
+    pub fn get_transaction_count(env: Env) -> u32 {
+        env.storage().instance().get(&DataKey::TransactionCount).unwrap_or(0)
+    }
+
+    // Transactions touching `account` among positions start..start + limit
     pub fn get_transaction_history(
         env: Env,
         account: String,
+        start: u32,
+        limit: u32,
     ) -> Vec<FinancialTransaction> {
-        let transactions: Vec<FinancialTransaction> = env
-            .storage()
-            .instance()
-            .get(&Symbol::new(&env, "TRANSACTIONS"))
-            .unwrap_or_else(|| Vec::new(&env));
+        let end = Self::get_transaction_count(env.clone()).min(start.saturating_add(limit));
 
         let mut result = Vec::new(&env);
-        for i in 0..transactions.len() {
-            let transaction = transactions.get(i).unwrap();
+        for i in start..end {
+            let transaction: FinancialTransaction = env
+                .storage()
+                .persistent()
+                .get(&DataKey::Transaction(i))
+                .unwrap();
             if transaction.from_account == account || transaction.to_account == account {
-                result.push_back(transaction.clone());
+                result.push_back(transaction);
             }
         }
         result
@@ -150,11 +235,68 @@ impl AccountingSystem {
             return Ok(0);
         }
         
-        let profit_margin = (report.net_income * 100) / report.total_revenue;
+        let profit_margin = math::mul_div(report.net_income, 100, report.total_revenue)?;
         Ok(profit_margin)
     }
 
     pub fn hello(env: Env) -> String {
         String::from_str(&env, "Hello from Accounting System!")
     }
-}
\ No newline at end of file
+
+    fn _require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "ADMIN"))
+            .ok_or(Error::from_contract_error(2003))?;
+        if *admin != stored_admin {
+            return Err(Error::from_contract_error(2003)); // Unauthorized
+        }
+        Ok(())
+    }
+
+    fn _append_transaction(env: &Env, transaction: &FinancialTransaction) -> Result<(), Error> {
+        let totals_key = match transaction.transaction_type {
+            TransactionType::Revenue => Some(DataKey::TotalRevenue),
+            TransactionType::Expense | TransactionType::Tax | TransactionType::Payroll => Some(DataKey::TotalExpenses),
+            _ => None,
+        };
+        if let Some(totals_key) = totals_key {
+            let total: i128 = env.storage().instance().get(&totals_key).unwrap_or(0);
+            env.storage().instance().set(&totals_key, &math::add(total, transaction.amount)?);
+        }
+
+        let count = Self::get_transaction_count(env.clone());
+        let key = DataKey::Transaction(count);
+        env.storage().persistent().set(&key, transaction);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        env.storage().instance().set(&DataKey::TransactionCount, &math::add_u32(count, 1)?);
+        Ok(())
+    }
+
+    // `prefix` followed by the batch id in decimal, e.g. SETTLEMENT_GROSS_42
+    fn _settlement_transaction_id(env: &Env, prefix: &str, batch_id: u32) -> String {
+        let mut buffer = [0u8; 32];
+        let prefix_len = prefix.len();
+        buffer[..prefix_len].copy_from_slice(prefix.as_bytes());
+
+        let mut digits = [0u8; 10];
+        let mut digit_count = 0;
+        let mut remaining = batch_id;
+        loop {
+            digits[digit_count] = b'0' + (remaining % 10) as u8;
+            digit_count += 1;
+            remaining /= 10;
+            if remaining == 0 {
+                break;
+            }
+        }
+        for i in 0..digit_count {
+            buffer[prefix_len + i] = digits[digit_count - 1 - i];
+        }
+
+        String::from_bytes(env, &buffer[..prefix_len + digit_count])
+    }
+}
+
+mod test;
\ No newline at end of file
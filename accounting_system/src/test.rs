@@ -0,0 +1,53 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{testutils::Address as _, Env};
+
+fn report(env: &Env, batch_id: u32, net_amount: i128) -> SettlementReport {
+    SettlementReport {
+        batch_id,
+        merchant: Address::generate(env),
+        token: Address::generate(env),
+        period_start: 0,
+        period_end: 86_400,
+        payment_count: 3,
+        gross_amount: 1_000,
+        fee_amount: 30,
+        refund_amount: 100,
+        net_amount,
+    }
+}
+
+#[test]
+fn test_import_settlement_report() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let contract_id = env.register_contract(None, AccountingSystem);
+    let client = AccountingSystemClient::new(&env, &contract_id);
+    let admin = Address::generate(&env);
+    let payment_processor = Address::generate(&env);
+    client.initialize(&admin);
+
+    // Only the admin or the configured payment_processor may post batches
+    assert!(client.try_import_settlement_report(&payment_processor, &report(&env, 7, 870)).is_err());
+    client.set_payment_processor(&admin, &payment_processor);
+    assert!(client.try_import_settlement_report(&Address::generate(&env), &report(&env, 7, 870)).is_err());
+
+    assert!(client.try_import_settlement_report(&payment_processor, &report(&env, 7, 900)).is_err());
+    client.import_settlement_report(&payment_processor, &report(&env, 7, 870));
+    assert!(client.try_import_settlement_report(&admin, &report(&env, 7, 870)).is_err());
+    client.import_settlement_report(&admin, &report(&env, 12, 870));
+
+    let revenue = client.get_transaction_history(&String::from_str(&env, "sales_revenue"), &0, &10);
+    assert_eq!(revenue.len(), 6);
+    assert_eq!(revenue.get(0).unwrap().transaction_id, String::from_str(&env, "SETTLEMENT_GROSS_7"));
+    assert_eq!(revenue.get(5).unwrap().transaction_id, String::from_str(&env, "SETTLEMENT_REFUNDS_12"));
+
+    assert_eq!(client.get_transaction_count(), 6);
+    assert_eq!(client.get_transaction_history(&String::from_str(&env, "sales_revenue"), &4, &10).len(), 2);
+    assert_eq!(client.generate_financial_report(&String::from_str(&env, "all")).total_revenue, 2_000);
+
+    let mut overflowing = report(&env, 8, 0);
+    overflowing.gross_amount = i128::MIN;
+    assert!(client.try_import_settlement_report(&admin, &overflowing).is_err());
+}
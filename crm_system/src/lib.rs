@@ -3,6 +3,7 @@ use soroban_sdk::{
     contract, contractimpl, contracttype, 
     Env, String, Address, Vec, Symbol, Map, Error
 };
+use shared::{math, DashboardClient, LoyaltyLedgerClient, MetricEvent};
 
 #[contract]
 pub struct CrmSystem;
@@ -53,6 +54,16 @@ pub enum Preference {
     NewArrivalAlerts,
 }
 
+// One customer whose cached CRM points differ from the loyalty_tokens ledger
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointsMismatch {
+    pub wallet_address: Address,
+    pub crm_points: u32,
+    pub ledger_points: i128,
+}
+
+// Status is not customer-editable: the admin sets it through set_customer_status
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub struct CustomerUpdate {
@@ -60,13 +71,19 @@ pub struct CustomerUpdate {
     pub email: Option<String>,
     pub phone: Option<String>,
     pub preferences: Option<Vec<Preference>>,
-    pub status: Option<CustomerStatus>,
 }
 
 #[contractimpl]
 impl CrmSystem {
-    pub fn initialize(env: Env, admin: Address) {
-        env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        let admin_key = Symbol::new(&env, "ADMIN");
+        if env.storage().instance().has(&admin_key) {
+            return Err(Error::from_contract_error(1007)); // Already initialized
+        }
+
+        admin.require_auth();
+        env.storage().instance().set(&admin_key, &admin);
+        Ok(())
     }
 
     pub fn register_customer(
@@ -78,6 +95,7 @@ impl CrmSystem {
         phone: String,
     ) -> Result<Customer, Error> {
         admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
 
         let customers_key = Symbol::new(&env, "CUSTOMERS");
         let mut customers: Map<Address, Customer> = env
@@ -93,7 +111,7 @@ impl CrmSystem {
 
         // Tạo customer ID đơn giản
         let timestamp = env.ledger().timestamp();
-        let customer_id = if timestamp % 2 == 0 {
+        let customer_id = if timestamp.is_multiple_of(2) {
             String::from_str(&env, "CUST_EVEN")
         } else {
             String::from_str(&env, "CUST_ODD")
@@ -108,7 +126,7 @@ impl CrmSystem {
             join_date: timestamp,
             total_spent: 0,
             total_orders: 0,
-            loyalty_points: 100,
+            loyalty_points: 0,
             customer_tier: CustomerTier::Bronze,
             preferences: Vec::from_array(&env, [
                 Preference::EmailNotifications,
@@ -118,9 +136,18 @@ impl CrmSystem {
             status: CustomerStatus::Active,
         };
 
+        // Once connected to loyalty_tokens the welcome points live on the ledger
+        let customer = Self::_issue_points(&env, customer, 100, "welcome")?;
+
         customers.set(wallet_address, customer.clone());
         env.storage().instance().set(&customers_key, &customers);
 
+        // A failing dashboard must not block the registration
+        if let Some(dashboard) = Self::get_dashboard(env.clone()) {
+            let _ = DashboardClient::new(&env, &dashboard)
+                .try_record_event(&env.current_contract_address(), &MetricEvent::CustomerRegistered);
+        }
+
         Ok(customer)
     }
 
@@ -154,9 +181,32 @@ impl CrmSystem {
         if let Some(preferences) = updates.preferences {
             customer.preferences = preferences;
         }
-        if let Some(status) = updates.status {
-            customer.status = status;
-        }
+
+        customers.set(customer_address, customer.clone());
+        env.storage().instance().set(&customers_key, &customers);
+
+        Ok(customer)
+    }
+
+    pub fn set_customer_status(
+        env: Env,
+        admin: Address,
+        customer_address: Address,
+        status: CustomerStatus,
+    ) -> Result<Customer, Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let customers_key = Symbol::new(&env, "CUSTOMERS");
+        let mut customers: Map<Address, Customer> = env
+            .storage()
+            .instance()
+            .get(&customers_key)
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut customer = customers.get(customer_address.clone())
+            .ok_or(Error::from_contract_error(1002))?; // Customer not found
+        customer.status = status;
 
         customers.set(customer_address, customer.clone());
         env.storage().instance().set(&customers_key, &customers);
@@ -166,10 +216,16 @@ impl CrmSystem {
 
     pub fn record_purchase(
         env: Env,
-        _pos_system: Address,
+        pos_system: Address,
         customer_address: Address,
         amount: i128,
     ) -> Result<Customer, Error> {
+        // Purchases mint loyalty points, so only the admin or the registered POS may report them
+        pos_system.require_auth();
+        if env.storage().instance().get::<Symbol, Address>(&Symbol::new(&env, "POS_SYSTEM")) != Some(pos_system.clone()) {
+            Self::_require_admin(&env, &pos_system)?;
+        }
+
         let customers_key = Symbol::new(&env, "CUSTOMERS");
         let mut customers: Map<Address, Customer> = env
             .storage()
@@ -181,13 +237,13 @@ impl CrmSystem {
             .ok_or(Error::from_contract_error(1002))?; // Customer not found
 
         // Update customer stats
-        customer.total_spent += amount;
-        customer.total_orders += 1;
+        customer.total_spent = math::add(customer.total_spent, amount)?;
+        customer.total_orders = math::add_u32(customer.total_orders, 1)?;
         customer.last_purchase_date = env.ledger().timestamp();
 
         // Add loyalty points (1 point per 10,000 VND)
-        let points_earned = amount / 10_000;
-        customer.loyalty_points += points_earned as u32;
+        let points_earned = math::to_u32(amount / 10_000)?;
+        let mut customer = Self::_issue_points(&env, customer, points_earned, "purchase")?;
 
         // Update customer tier based on total spent
         customer.customer_tier = match customer.total_spent {
@@ -210,11 +266,21 @@ impl CrmSystem {
             .get(&Symbol::new(&env, "CUSTOMERS"))
             .unwrap_or_else(|| Map::new(&env));
 
-        customers.get(wallet_address)
-            .ok_or(Error::from_contract_error(1002)) // Customer not found
+        let mut customer = customers.get(wallet_address)
+            .ok_or(Error::from_contract_error(1002))?; // Customer not found
+
+        // loyalty_tokens is the source of truth for points once connected
+        if let Some(ledger) = Self::_loyalty_ledger(&env) {
+            customer.loyalty_points = math::to_u32(ledger.get_balance(&customer.wallet_address))?;
+        }
+        Ok(customer)
+    }
+
+    pub fn get_customer_tier(env: Env, wallet_address: Address) -> Result<CustomerTier, Error> {
+        Ok(Self::get_customer(env, wallet_address)?.customer_tier)
     }
 
-    pub fn get_customers_by_tier(env: Env, tier: CustomerTier) -> Vec<Customer> {
+    pub fn get_customers_by_tier(env: Env, tier: CustomerTier) -> Result<Vec<Customer>, Error> {
         let customers: Map<Address, Customer> = env
             .storage()
             .instance()
@@ -227,10 +293,10 @@ impl CrmSystem {
                 result.push_back(customer);
             }
         }
-        result
+        Self::_refresh_points(&env, result)
     }
 
-    pub fn get_vip_customers(env: Env) -> Vec<Customer> {
+    pub fn get_vip_customers(env: Env) -> Result<Vec<Customer>, Error> {
         let customers: Map<Address, Customer> = env
             .storage()
             .instance()
@@ -243,10 +309,10 @@ impl CrmSystem {
                 result.push_back(customer);
             }
         }
-        result
+        Self::_refresh_points(&env, result)
     }
 
-    pub fn get_customer_ranking(env: Env) -> Vec<Customer> {
+    pub fn get_customer_ranking(env: Env) -> Result<Vec<Customer>, Error> {
         let customers: Map<Address, Customer> = env
             .storage()
             .instance()
@@ -254,7 +320,7 @@ impl CrmSystem {
             .unwrap_or_else(|| Map::new(&env));
 
         let customer_list: Vec<Customer> = customers.values();
-        customer_list
+        Self::_refresh_points(&env, customer_list)
     }
 
     pub fn add_loyalty_points(
@@ -264,6 +330,7 @@ impl CrmSystem {
         points: u32,
     ) -> Result<Customer, Error> {
         admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
 
         let customers_key = Symbol::new(&env, "CUSTOMERS");
         let mut customers: Map<Address, Customer> = env
@@ -272,10 +339,10 @@ impl CrmSystem {
             .get(&customers_key)
             .unwrap_or_else(|| Map::new(&env));
 
-        let mut customer = customers.get(customer_address.clone())
+        let customer = customers.get(customer_address.clone())
             .ok_or(Error::from_contract_error(1002))?; // Customer not found
         
-        customer.loyalty_points += points;
+        let customer = Self::_issue_points(&env, customer, points, "crm_adjustment")?;
 
         customers.set(customer_address, customer.clone());
         env.storage().instance().set(&customers_key, &customers);
@@ -283,6 +350,174 @@ impl CrmSystem {
         Ok(customer)
     }
 
+    // One-off: moves every CRM balance onto loyalty_tokens, `limit` customers per call so each
+    // batch fits the issuer's daily cap; returns how many customers are still to be moved.
+    // CRM only mirrors the ledger once the last batch is done, and point changes are rejected
+    // until then. This contract must be registered as an issuer in loyalty_tokens.
+    pub fn migrate_to_loyalty_token(env: Env, admin: Address, loyalty_token: Address, limit: u32) -> Result<u32, Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let loyalty_key = Symbol::new(&env, "LOYALTY_TOKEN");
+        if env.storage().instance().has(&loyalty_key) {
+            return Err(Error::from_contract_error(1004)); // Already migrated
+        }
+        let target_key = Symbol::new(&env, "MIGRATION_TARGET");
+        let target: Address = env.storage().instance().get(&target_key).unwrap_or(loyalty_token.clone());
+        if target != loyalty_token {
+            return Err(Error::from_contract_error(1006)); // Migration in progress
+        }
+        env.storage().instance().set(&target_key, &loyalty_token);
+
+        let customers_key = Symbol::new(&env, "CUSTOMERS");
+        let mut customers: Map<Address, Customer> = env
+            .storage()
+            .instance()
+            .get(&customers_key)
+            .unwrap_or_else(|| Map::new(&env));
+
+        // Registrations are blocked while migrating, so positions in the map stay put
+        let cursor_key = Symbol::new(&env, "MIGRATION_CURSOR");
+        let cursor: u32 = env.storage().instance().get(&cursor_key).unwrap_or(0);
+        let wallets = customers.keys();
+        let end = wallets.len().min(cursor.saturating_add(limit));
+
+        let ledger = LoyaltyLedgerClient::new(&env, &loyalty_token);
+        for i in cursor..end {
+            let wallet_address = wallets.get(i).unwrap();
+            let mut customer = customers.get(wallet_address.clone()).unwrap();
+            if customer.loyalty_points > 0 {
+                ledger.issue_points(
+                    &env.current_contract_address(),
+                    &wallet_address,
+                    &(customer.loyalty_points as i128),
+                    &String::from_str(&env, "crm_migration"),
+                    &customer.customer_id,
+                );
+            }
+            customer.loyalty_points = math::to_u32(ledger.get_balance(&wallet_address))?;
+            customers.set(wallet_address, customer);
+        }
+        env.storage().instance().set(&customers_key, &customers);
+
+        let remaining = wallets.len() - end;
+        if remaining == 0 {
+            env.storage().instance().set(&loyalty_key, &loyalty_token);
+            env.storage().instance().remove(&target_key);
+            env.storage().instance().remove(&cursor_key);
+        } else {
+            env.storage().instance().set(&cursor_key, &end);
+        }
+
+        Ok(remaining)
+    }
+
+    // Customers whose cached CRM points disagree with loyalty_tokens
+    pub fn reconcile_loyalty_points(env: Env) -> Result<Vec<PointsMismatch>, Error> {
+        let ledger = Self::_loyalty_ledger(&env)
+            .ok_or(Error::from_contract_error(1005))?; // Loyalty token not set
+        let customers: Map<Address, Customer> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "CUSTOMERS"))
+            .unwrap_or_else(|| Map::new(&env));
+
+        let mut result = Vec::new(&env);
+        for customer in customers.values() {
+            let ledger_points = ledger.get_balance(&customer.wallet_address);
+            if ledger_points != customer.loyalty_points as i128 {
+                result.push_back(PointsMismatch {
+                    wallet_address: customer.wallet_address,
+                    crm_points: customer.loyalty_points,
+                    ledger_points,
+                });
+            }
+        }
+        Ok(result)
+    }
+
+    pub fn set_pos_system(env: Env, admin: Address, pos_system: Address) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "POS_SYSTEM"), &pos_system);
+        Ok(())
+    }
+
+    // New registrations are reported to this dashboard as they happen
+    pub fn set_dashboard(env: Env, admin: Address, dashboard: Address) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "DASHBOARD"), &dashboard);
+        Ok(())
+    }
+
+    pub fn get_dashboard(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Symbol::new(&env, "DASHBOARD"))
+    }
+
+    pub fn get_loyalty_token(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Symbol::new(&env, "LOYALTY_TOKEN"))
+    }
+
+    fn _loyalty_ledger(env: &Env) -> Option<LoyaltyLedgerClient<'_>> {
+        Self::get_loyalty_token(env.clone()).map(|address| LoyaltyLedgerClient::new(env, &address))
+    }
+
+    // Credits points on loyalty_tokens when connected (and refreshes the cached copy),
+    // otherwise on the CRM record itself as before the migration
+    fn _issue_points(env: &Env, mut customer: Customer, points: u32, reason: &str) -> Result<Customer, Error> {
+        if env.storage().instance().has(&Symbol::new(env, "MIGRATION_TARGET")) {
+            return Err(Error::from_contract_error(1006)); // Migration in progress
+        }
+
+        match Self::_loyalty_ledger(env) {
+            Some(ledger) => {
+                if points > 0 {
+                    ledger.issue_points(
+                        &env.current_contract_address(),
+                        &customer.wallet_address,
+                        &(points as i128),
+                        &String::from_str(env, reason),
+                        &customer.customer_id,
+                    );
+                }
+                customer.loyalty_points = math::to_u32(ledger.get_balance(&customer.wallet_address))?;
+            }
+            None => {
+                customer.loyalty_points = math::add_u32(customer.loyalty_points, points)?;
+            }
+        }
+        Ok(customer)
+    }
+
+    // Replaces the cached points with the loyalty_tokens balance once connected
+    fn _refresh_points(env: &Env, customers: Vec<Customer>) -> Result<Vec<Customer>, Error> {
+        let Some(ledger) = Self::_loyalty_ledger(env) else {
+            return Ok(customers);
+        };
+
+        let mut result = Vec::new(env);
+        for mut customer in customers.iter() {
+            customer.loyalty_points = math::to_u32(ledger.get_balance(&customer.wallet_address))?;
+            result.push_back(customer);
+        }
+        Ok(result)
+    }
+
+    fn _require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "ADMIN"))
+            .ok_or(Error::from_contract_error(1003))?;
+        if *admin != stored_admin {
+            return Err(Error::from_contract_error(1003)); // Unauthorized
+        }
+        Ok(())
+    }
+
     pub fn get_total_customers(env: Env) -> u32 {
         let customers: Map<Address, Customer> = env
             .storage()
@@ -290,7 +525,7 @@ impl CrmSystem {
             .get(&Symbol::new(&env, "CUSTOMERS"))
             .unwrap_or_else(|| Map::new(&env));
 
-        customers.len() as u32
+        customers.len()
     }
 
     pub fn get_customers_count_by_tier(env: Env) -> Map<CustomerTier, u32> {
@@ -326,4 +561,6 @@ impl CrmSystem {
     pub fn hello(env: Env) -> String {
         String::from_str(&env, "Hello from CRM System!")
     }
-}
\ No newline at end of file
+}
+
+mod test;
@@ -1,21 +1,154 @@
 #![cfg(test)]
 
 use super::*;
-use soroban_sdk::{vec, Env, String};
+use proptest::prelude::*;
+use soroban_sdk::{contract, contractimpl, testutils::Address as _, Env, String};
+
+fn setup_customer<'a>(env: &Env) -> (CrmSystemClient<'a>, Address, Address) {
+    let contract_id = env.register_contract(None, CrmSystem);
+    let client = CrmSystemClient::new(env, &contract_id);
+    let admin = Address::generate(env);
+    let customer = Address::generate(env);
+    client.initialize(&admin);
+    client.register_customer(
+        &admin,
+        &customer,
+        &String::from_str(env, "Lan"),
+        &String::from_str(env, "lan@example.com"),
+        &String::from_str(env, "0900000000"),
+    );
+    (client, admin, customer)
+}
+
+// Stand-in for loyalty_tokens keeping plain balances
+#[contract]
+struct MockLoyaltyLedger;
+
+#[contractimpl]
+impl MockLoyaltyLedger {
+    pub fn get_balance(env: Env, owner: Address) -> i128 {
+        env.storage().instance().get(&owner).unwrap_or(0)
+    }
+
+    pub fn issue_points(env: Env, issuer: Address, to: Address, amount: i128, _reason: String, _reference_id: String) {
+        issuer.require_auth();
+        let balance = Self::get_balance(env.clone(), to.clone());
+        env.storage().instance().set(&to, &(balance + amount));
+    }
+}
 
 #[test]
 fn test() {
     let env = Env::default();
-    let contract_id = env.register(Contract, ());
-    let client = ContractClient::new(&env, &contract_id);
-
-    let words = client.hello(&String::from_str(&env, "Dev"));
-    assert_eq!(
-        words,
-        vec![
-            &env,
-            String::from_str(&env, "Hello"),
-            String::from_str(&env, "Dev"),
-        ]
+    let contract_id = env.register_contract(None, CrmSystem);
+    let client = CrmSystemClient::new(&env, &contract_id);
+
+    assert_eq!(client.hello(), String::from_str(&env, "Hello from CRM System!"));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_record_purchase_never_wraps(first in 0..=i128::MAX, second in 0..=i128::MAX) {
+        let env = Env::default();
+        env.mock_all_auths();
+        let (client, admin, customer) = setup_customer(&env);
+
+        // Each purchase must fit total_spent and the u32 points counter, or be rejected whole
+        let mut expected = client.get_customer(&customer);
+        for amount in [first, second] {
+            let points = u32::try_from(amount / 10_000).ok().and_then(|p| expected.loyalty_points.checked_add(p));
+            let total_spent = expected.total_spent.checked_add(amount);
+
+            let result = client.try_record_purchase(&admin, &customer, &amount);
+            prop_assert_eq!(result.is_ok(), points.is_some() && total_spent.is_some());
+            if let (Some(points), Some(total_spent)) = (points, total_spent) {
+                expected.loyalty_points = points;
+                expected.total_spent = total_spent;
+            }
+
+            let stored = client.get_customer(&customer);
+            prop_assert_eq!((stored.loyalty_points, stored.total_spent), (expected.loyalty_points, expected.total_spent));
+        }
+    }
+}
+
+#[test]
+fn test_add_loyalty_points_overflow() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, customer) = setup_customer(&env);
+
+    assert!(client.try_add_loyalty_points(&admin, &customer, &u32::MAX).is_err());
+    assert_eq!(client.get_customer(&customer).loyalty_points, 100);
+}
+
+#[test]
+fn test_points_require_admin() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, customer) = setup_customer(&env);
+    let stranger = Address::generate(&env);
+
+    // Nobody can take over the admin slot once it is set
+    assert!(client.try_initialize(&stranger).is_err());
+    assert!(client.try_add_loyalty_points(&stranger, &customer, &500).is_err());
+    let result = client.try_register_customer(
+        &stranger,
+        &Address::generate(&env),
+        &String::from_str(&env, "Minh"),
+        &String::from_str(&env, "minh@example.com"),
+        &String::from_str(&env, "0911111111"),
     );
+    assert!(result.is_err());
+    assert_eq!(client.get_customer(&customer).loyalty_points, 100);
+    assert_eq!(client.get_total_customers(), 1);
+}
+
+#[test]
+fn test_points_move_to_loyalty_token() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, customer) = setup_customer(&env);
+    client.add_loyalty_points(&admin, &customer, &50);
+
+    let second = Address::generate(&env);
+    client.register_customer(
+        &admin,
+        &second,
+        &String::from_str(&env, "Minh"),
+        &String::from_str(&env, "minh@example.com"),
+        &String::from_str(&env, "0911111111"),
+    );
+
+    // One customer per batch; points stay frozen until the last batch connects the ledger
+    let ledger_id = env.register_contract(None, MockLoyaltyLedger);
+    let ledger = MockLoyaltyLedgerClient::new(&env, &ledger_id);
+    assert_eq!(client.migrate_to_loyalty_token(&admin, &ledger_id, &1), 1);
+    assert_eq!(client.get_loyalty_token(), None);
+    assert!(client.try_add_loyalty_points(&admin, &customer, &10).is_err());
+    assert!(client.try_migrate_to_loyalty_token(&admin, &env.register_contract(None, MockLoyaltyLedger), &1).is_err());
+    assert_eq!(client.migrate_to_loyalty_token(&admin, &ledger_id, &1), 0);
+    assert_eq!(client.get_loyalty_token(), Some(ledger_id.clone()));
+    assert!(client.try_migrate_to_loyalty_token(&admin, &ledger_id, &1).is_err());
+    assert_eq!(ledger.get_balance(&customer), 150);
+    assert_eq!(ledger.get_balance(&second), 100);
+
+    // Purchases now credit the ledger and CRM reads its points back from there
+    client.record_purchase(&admin, &customer, &100_000);
+    assert_eq!(ledger.get_balance(&customer), 160);
+    assert_eq!(client.get_customer(&customer).loyalty_points, 160);
+    assert!(client.try_record_purchase(&Address::generate(&env), &customer, &100_000).is_err());
+    assert_eq!(client.reconcile_loyalty_points().len(), 0);
+
+    ledger.issue_points(&admin, &customer, &5, &String::from_str(&env, "elsewhere"), &String::from_str(&env, ""));
+    let mismatches = client.reconcile_loyalty_points();
+    assert_eq!(mismatches.len(), 1);
+    assert_eq!(mismatches.get(0).unwrap().ledger_points, 165);
+
+    // Listings read the ledger rather than the cached copy
+    let ranking = client.get_customer_ranking();
+    let listed = ranking.iter().find(|listed| listed.wallet_address == customer).unwrap();
+    assert_eq!(listed.loyalty_points, 165);
 }
@@ -1,5 +1,6 @@
 ﻿#![no_std]
-use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, String, Symbol, Map, Error};
+use soroban_sdk::{contract, contractclient, contractimpl, contracttype, Address, Env, Map, String, Symbol, Vec, Error};
+use shared::math;
 
 #[contract]
 pub struct Dashboard;
@@ -17,28 +18,1047 @@ pub struct RealTimeMetrics {
     pub online_visitors: u32,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataSources {
+    pub pos_system: Address,
+    pub inventory_manager: Address,
+    pub crm_system: Address,
+    pub payment_processor: Address,
+}
+
+// Reported by the source contracts through record_event
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricEvent {
+    Sale(i128),                 // final amount, from pos_system or a store's POS
+    CustomerRegistered,         // from crm_system
+    StockChanged(String, u32),  // sku and new quantity, from inventory_manager
+    Refund(i128),               // refunded amount, from payment_processor
+}
+
+// Rolling counters for one day or one hour
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeriodMetrics {
+    pub period_start: u64,
+    pub sales_count: u32,
+    pub revenue: i128,
+    pub new_customers: u32,
+    pub refund_count: u32,
+    pub refunded_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TargetPeriod {
+    Daily,
+    Weekly,  // Monday to Sunday, UTC
+    Monthly, // calendar month, UTC
+}
+
+// Current minus the compared day; positive means today is ahead
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricsDelta {
+    pub sales_count: i128,
+    pub revenue: i128,
+    pub average_order_value: i128,
+    pub new_customers: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TargetProgress {
+    pub period: TargetPeriod,
+    pub period_start: u64,
+    pub target: i128,
+    pub achieved: i128,
+    pub attainment_bps: i128, // 10_000 = target met
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetricsComparison {
+    pub current: RealTimeMetrics,
+    pub vs_yesterday: MetricsDelta,
+    pub vs_last_week: MetricsDelta, // same weekday one week earlier
+    pub targets: Vec<TargetProgress>,
+}
+
+// One tile on a dashboard view
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Widget {
+    SalesCount,
+    Revenue,
+    AverageOrderValue,
+    NewCustomers,
+    LowStockAlerts,
+    PendingOrders,
+    OnlineVisitors,
+}
+
+impl Widget {
+    // Financial widgets are only shown to staff with ViewReports
+    pub fn is_financial(&self) -> bool {
+        matches!(self, Widget::Revenue | Widget::AverageOrderValue)
+    }
+
+    // Alert counters breach their threshold by going over it, everything else by going under
+    fn breaches(&self, value: i128, threshold: i128) -> bool {
+        match self {
+            Widget::LowStockAlerts | Widget::PendingOrders => value > threshold,
+            _ => value < threshold,
+        }
+    }
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DashboardLayout {
+    pub widgets: Vec<Widget>,
+    pub thresholds: Map<Widget, i128>,
+    pub stores: Vec<String>, // sales widgets only count these stores; empty means every sale
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct WidgetValue {
+    pub widget: Widget,
+    pub value: i128,
+    pub threshold_breached: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DashboardView {
+    pub widgets: Vec<WidgetValue>,
+    pub restricted: Vec<Widget>, // in the layout but hidden from this viewer
+    pub stores: Vec<String>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AlertCondition {
+    RevenueBelow(i128, u64), // today's revenue under the amount once this many seconds of the UTC day have passed
+    StockBelow(String, u32), // last reported stock of the sku under its reorder point
+    RefundRateAbove(u32),    // today's refunded amount over this many basis points of revenue
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertRule {
+    pub rule_id: u32,
+    pub condition: AlertCondition,
+    pub severity: AlertSeverity,
+    pub assignee: Option<Address>,
+    pub active: bool,
+    pub last_triggered: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AlertStatus {
+    Open,
+    Acknowledged,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Alert {
+    pub alert_id: u32,
+    pub rule_id: u32,
+    pub severity: AlertSeverity,
+    pub assignee: Option<Address>,
+    pub value: i128, // the metric that tripped the rule
+    pub raised_at: u64,
+    pub status: AlertStatus,
+    pub acknowledged_by: Option<Address>,
+    pub acknowledged_at: u64,
+}
+
+// Mirrors employee_manager::EmployeeRole
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EmployeeRole {
+    SuperAdmin,
+    StoreManager,
+    DepartmentManager,
+    Cashier,
+    StockClerk,
+    SalesAssociate,
+    HRManager,
+    Accountant,
+}
+
+// Mirrors employee_manager::Permission
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    ManageEmployees,
+    ProcessPayments,
+    ManageInventory,
+    ViewReports,
+    ManageVendors,
+    IssueLoyaltyPoints,
+    ProcessPayroll,
+    SystemAdmin,
+    FulfillRedemptions,
+}
+
+#[contractclient(name = "EmployeeManagerClient")]
+pub trait EmployeeManagerInterface {
+    fn has_permission(env: Env, employee_address: Address, required_permission: Permission) -> bool;
+    fn get_employee_role(env: Env, wallet_address: Address) -> EmployeeRole;
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Day(u64),
+    Hour(u64),
+    LowStock(String),
+    Stock(String),
+    Snapshot(u64),
+    Target(String, TargetPeriod),
+    Rule(u32),
+    StockRules(String),
+    RefundRules,
+    Alert(u32),
+    StorePos(Address),
+    StoreDay(String, u64),
+    RoleLayout(EmployeeRole),
+    UserLayout(Address),
+}
+
+const DAY_IN_SECONDS: u64 = 86_400;
+const HOUR_IN_SECONDS: u64 = 3_600;
+const BUMP_THRESHOLD: u32 = 17_280; // ~1 day
+const BUMP_AMOUNT: u32 = 518_400; // ~30 days
+const MAX_RULES_PER_TRIGGER: u32 = 5; // bounds the rules evaluated by one recorded event
+const MAX_LAYOUT_STORES: u32 = 10; // bounds the store counters read by one view
+
 #[contractimpl]
 impl Dashboard {
-    pub fn initialize(env: Env, admin: Address) {
-        env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
+    pub fn initialize(env: Env, admin: Address) -> Result<(), Error> {
+        let admin_key = Symbol::new(&env, "ADMIN");
+        if env.storage().instance().has(&admin_key) {
+            return Err(Error::from_contract_error(4011)); // Already initialized
+        }
+
+        admin.require_auth();
+        env.storage().instance().set(&admin_key, &admin);
+        Ok(())
+    }
+
+    pub fn set_data_sources(env: Env, admin: Address, sources: DataSources) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "DATA_SOURCES"), &sources);
+        Ok(())
+    }
+
+    pub fn get_data_sources(env: Env) -> Result<DataSources, Error> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "DATA_SOURCES"))
+            .ok_or(Error::from_contract_error(4001)) // Data sources not configured
+    }
+
+    // Products at or below this many units count as a low-stock alert;
+    // applies to stock changes reported after it is set
+    pub fn set_low_stock_threshold(env: Env, admin: Address, threshold: u32) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "LOW_STOCK_THRESHOLD"), &threshold);
+        Ok(())
+    }
+
+    pub fn get_low_stock_threshold(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "LOW_STOCK_THRESHOLD"))
+            .unwrap_or(10)
+    }
+
+    // Sales reported by this POS contract also count towards the store's own counters
+    pub fn set_store_pos(env: Env, admin: Address, pos: Address, store: String) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let key = DataKey::StorePos(pos);
+        env.storage().persistent().set(&key, &store);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    pub fn get_store_pos(env: Env, pos: Address) -> Option<String> {
+        env.storage().persistent().get(&DataKey::StorePos(pos))
+    }
+
+    // Called by the configured source contracts on every sale, registration and stock change
+    pub fn record_event(env: Env, source: Address, event: MetricEvent) -> Result<(), Error> {
+        source.require_auth();
+        let sources = Self::get_data_sources(env.clone())?;
+
+        let store = match &event {
+            MetricEvent::Sale(_) => Self::get_store_pos(env.clone(), source.clone()),
+            _ => None,
+        };
+        let expected_source = match &event {
+            MetricEvent::Sale(_) => &sources.pos_system,
+            MetricEvent::CustomerRegistered => &sources.crm_system,
+            MetricEvent::StockChanged(_, _) => &sources.inventory_manager,
+            MetricEvent::Refund(_) => &sources.payment_processor,
+        };
+        if source != *expected_source && store.is_none() {
+            return Err(Error::from_contract_error(4002)); // Unauthorized
+        }
+
+        let now = env.ledger().timestamp();
+        let day_start = now - now % DAY_IN_SECONDS;
+        let hour_start = now - now % HOUR_IN_SECONDS;
+        Self::_roll_over(&env, day_start);
+
+        let rule_ids = match &event {
+            MetricEvent::StockChanged(sku, _) => Self::_rule_index(&env, &DataKey::StockRules(sku.clone())),
+            MetricEvent::Refund(_) => Self::_rule_index(&env, &DataKey::RefundRules),
+            MetricEvent::Sale(_) | MetricEvent::CustomerRegistered => Vec::new(&env),
+        };
+        match event {
+            MetricEvent::Sale(amount) => {
+                let mut keys = Vec::from_array(&env, [(DataKey::Day(day_start), day_start), (DataKey::Hour(hour_start), hour_start)]);
+                if let Some(store) = store {
+                    keys.push_back((DataKey::StoreDay(store, day_start), day_start));
+                }
+                for (key, start) in keys.iter() {
+                    let mut metrics = Self::_load_period(&env, &key, start);
+                    metrics.sales_count = Self::_add_u32(metrics.sales_count, 1)?;
+                    metrics.revenue = Self::_add(metrics.revenue, amount)?;
+                    Self::_save_period(&env, &key, &metrics);
+                }
+            }
+            MetricEvent::CustomerRegistered => {
+                for (key, start) in [(DataKey::Day(day_start), day_start), (DataKey::Hour(hour_start), hour_start)] {
+                    let mut metrics = Self::_load_period(&env, &key, start);
+                    metrics.new_customers = Self::_add_u32(metrics.new_customers, 1)?;
+                    Self::_save_period(&env, &key, &metrics);
+                }
+            }
+            MetricEvent::StockChanged(sku, quantity) => {
+                Self::_update_low_stock(&env, sku, quantity)?;
+            }
+            MetricEvent::Refund(amount) => {
+                for (key, start) in [(DataKey::Day(day_start), day_start), (DataKey::Hour(hour_start), hour_start)] {
+                    let mut metrics = Self::_load_period(&env, &key, start);
+                    metrics.refund_count = Self::_add_u32(metrics.refund_count, 1)?;
+                    metrics.refunded_amount = Self::_add(metrics.refunded_amount, amount)?;
+                    Self::_save_period(&env, &key, &metrics);
+                }
+            }
+        }
+
+        // Only the few rules this event can trip are evaluated
+        Self::_evaluate_rules(&env, rule_ids);
+        Ok(())
+    }
+
+    // "Today" is the current UTC day of the ledger clock. Revenue figures, like every
+    // metrics getter below, are only readable by the admin or staff with ViewReports.
+    pub fn get_real_time_metrics(env: Env, viewer: Address) -> Result<RealTimeMetrics, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+        Ok(Self::_real_time_metrics(&env))
+    }
+
+    // Metrics as of the last event recorded on the UTC day containing `timestamp`
+    pub fn get_daily_snapshot(env: Env, viewer: Address, timestamp: u64) -> Result<Option<RealTimeMetrics>, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+        Ok(Self::_daily_snapshot(&env, timestamp))
+    }
+
+    // Revenue target for every period of this kind until changed, measured against
+    // the sales of the store's POS (see set_store_pos)
+    pub fn set_sales_target(env: Env, admin: Address, store: String, period: TargetPeriod, revenue: i128) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        if revenue <= 0 {
+            return Err(Error::from_contract_error(4007)); // Invalid target
+        }
+
+        let key = DataKey::Target(store, period);
+        env.storage().persistent().set(&key, &revenue);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    pub fn get_sales_target(env: Env, store: String, period: TargetPeriod) -> Option<i128> {
+        env.storage().persistent().get(&DataKey::Target(store, period))
+    }
+
+    // Today's dashboard-wide metrics against yesterday and the same day last week,
+    // plus the store's progress towards each of its targets
+    pub fn get_metrics_comparison(env: Env, viewer: Address, store: String) -> Result<MetricsComparison, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+
+        let current = Self::_real_time_metrics(&env);
+        let now = env.ledger().timestamp();
+        let day_start = now - now % DAY_IN_SECONDS;
+
+        let mut targets = Vec::new(&env);
+        for period in [TargetPeriod::Daily, TargetPeriod::Weekly, TargetPeriod::Monthly] {
+            let Some(target) = Self::get_sales_target(env.clone(), store.clone(), period) else {
+                continue;
+            };
+
+            let period_start = Self::_period_start(day_start, period);
+            let mut achieved = 0;
+            let mut day = period_start;
+            while day <= day_start {
+                let store_day = Self::_load_period(&env, &DataKey::StoreDay(store.clone(), day), day);
+                achieved = Self::_add(achieved, store_day.revenue)?;
+                day += DAY_IN_SECONDS;
+            }
+
+            targets.push_back(TargetProgress {
+                period,
+                period_start,
+                target,
+                achieved,
+                attainment_bps: achieved.saturating_mul(10_000) / target,
+            });
+        }
+
+        Ok(MetricsComparison {
+            vs_yesterday: Self::_delta(&env, &current, day_start.saturating_sub(DAY_IN_SECONDS)),
+            vs_last_week: Self::_delta(&env, &current, day_start.saturating_sub(7 * DAY_IN_SECONDS)),
+            current,
+            targets,
+        })
+    }
+
+    // Counters for the UTC day containing `timestamp`
+    pub fn get_daily_metrics(env: Env, viewer: Address, timestamp: u64) -> Result<PeriodMetrics, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+        let day_start = timestamp - timestamp % DAY_IN_SECONDS;
+        Ok(Self::_load_period(&env, &DataKey::Day(day_start), day_start))
     }
 
-    pub fn get_real_time_metrics(env: Env) -> Result<RealTimeMetrics, Error> {
-        let metrics = RealTimeMetrics {
-            total_sales_today: 47,
-            total_customers_today: 124,
-            total_orders_today: 89,
-            revenue_today: 15_870_000,
-            average_order_value: 337_659,
-            low_stock_alerts: 12,
-            pending_orders: 8,
-            online_visitors: 23,
+    // Sales counters of one store for the UTC day containing `timestamp`
+    pub fn get_store_daily_metrics(env: Env, viewer: Address, store: String, timestamp: u64) -> Result<PeriodMetrics, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+        Ok(Self::_store_day(&env, store, timestamp))
+    }
+
+    // The 24 hourly counters of the UTC day containing `timestamp`
+    pub fn get_hourly_metrics(env: Env, viewer: Address, timestamp: u64) -> Result<Vec<PeriodMetrics>, Error> {
+        Self::_require_report_viewer(&env, &viewer)?;
+
+        let day_start = timestamp - timestamp % DAY_IN_SECONDS;
+        let mut hours = Vec::new(&env);
+        for hour in 0..24 {
+            let hour_start = day_start + hour * HOUR_IN_SECONDS;
+            hours.push_back(Self::_load_period(&env, &DataKey::Hour(hour_start), hour_start));
+        }
+        Ok(hours)
+    }
+
+    pub fn get_low_stock_count(env: Env) -> u32 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "LOW_STOCK_COUNT"))
+            .unwrap_or(0)
+    }
+
+    pub fn add_alert_rule(
+        env: Env,
+        admin: Address,
+        condition: AlertCondition,
+        severity: AlertSeverity,
+        assignee: Option<Address>,
+    ) -> Result<u32, Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let counter_key = Symbol::new(&env, "ALERT_RULE_COUNTER");
+        let rule_id = Self::_add_u32(env.storage().instance().get(&counter_key).unwrap_or(0), 1)?;
+
+        let rule = AlertRule {
+            rule_id,
+            condition,
+            severity,
+            assignee,
+            active: true,
+            last_triggered: 0,
         };
+        Self::_index_rule(&env, &rule)?;
+        Self::_save_rule(&env, &rule);
+        env.storage().instance().set(&counter_key, &rule_id);
+        Ok(rule_id)
+    }
+
+    pub fn set_alert_rule_active(env: Env, admin: Address, rule_id: u32, active: bool) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let mut rule: AlertRule = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Rule(rule_id))
+            .ok_or(Error::from_contract_error(4004))?; // Rule not found
+        if rule.active == active {
+            return Ok(());
+        }
+
+        // Inactive rules leave the event index so they free up its slots
+        if active {
+            Self::_index_rule(&env, &rule)?;
+        } else {
+            Self::_unindex_rule(&env, &rule);
+        }
+        rule.active = active;
+        Self::_save_rule(&env, &rule);
+        Ok(())
+    }
+
+    pub fn get_alert_rules(env: Env) -> Map<u32, AlertRule> {
+        let rule_count: u32 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "ALERT_RULE_COUNTER"))
+            .unwrap_or(0);
+
+        let mut rules = Map::new(&env);
+        for rule_id in 1..=rule_count {
+            if let Some(rule) = env.storage().persistent().get(&DataKey::Rule(rule_id)) {
+                rules.set(rule_id, rule);
+            }
+        }
+        rules
+    }
+
+    // Evaluates every rule. Stock and refund rules also run on the events that can trip
+    // them, but revenue deadlines only fire here, so a keeper should call this after each
+    // deadline. Each rule raises at most one alert per UTC day.
+    pub fn check_alerts(env: Env) -> Vec<Alert> {
+        let rule_count: u32 = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "ALERT_RULE_COUNTER"))
+            .unwrap_or(0);
+
+        let mut rule_ids = Vec::new(&env);
+        for rule_id in 1..=rule_count {
+            rule_ids.push_back(rule_id);
+        }
+        Self::_evaluate_rules(&env, rule_ids)
+    }
+
+    // Only the assignee (or the admin) can acknowledge an assigned alert
+    pub fn acknowledge_alert(env: Env, caller: Address, alert_id: u32) -> Result<Alert, Error> {
+        caller.require_auth();
+
+        let mut alert = Self::get_alert(env.clone(), alert_id)?;
+        if alert.status != AlertStatus::Open {
+            return Err(Error::from_contract_error(4006)); // Already acknowledged
+        }
+        if alert.assignee != Some(caller.clone()) {
+            Self::_require_admin(&env, &caller)?;
+        }
+
+        alert.status = AlertStatus::Acknowledged;
+        alert.acknowledged_by = Some(caller.clone());
+        alert.acknowledged_at = env.ledger().timestamp();
+        Self::_save_alert(&env, &alert);
+
+        let open_key = Symbol::new(&env, "OPEN_ALERTS");
+        let mut open_alerts = Self::_open_alert_ids(&env);
+        if let Some(index) = open_alerts.first_index_of(alert_id) {
+            open_alerts.remove(index);
+        }
+        env.storage().instance().set(&open_key, &open_alerts);
+
+        retail_events::publish(&env, retail_events::ALERT_ACKNOWLEDGED, &caller, alert.clone());
+        Ok(alert)
+    }
+
+    pub fn get_alert(env: Env, alert_id: u32) -> Result<Alert, Error> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Alert(alert_id))
+            .ok_or(Error::from_contract_error(4005)) // Alert not found
+    }
+
+    pub fn get_open_alerts(env: Env) -> Vec<Alert> {
+        let mut result = Vec::new(&env);
+        for alert_id in Self::_open_alert_ids(&env).iter() {
+            if let Ok(alert) = Self::get_alert(env.clone(), alert_id) {
+                result.push_back(alert);
+            }
+        }
+        result
+    }
+
+    pub fn set_employee_manager(env: Env, admin: Address, employee_manager: Address) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
 
-        Ok(metrics)
+        env.storage().instance().set(&Symbol::new(&env, "EMPLOYEE_MANAGER"), &employee_manager);
+        Ok(())
+    }
+
+    pub fn set_role_layout(env: Env, admin: Address, role: EmployeeRole, layout: DashboardLayout) -> Result<(), Error> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+        Self::_check_layout(&layout)?;
+
+        let key = DataKey::RoleLayout(role);
+        env.storage().persistent().set(&key, &layout);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    // A personal layout takes precedence over the one for the user's role
+    pub fn set_user_layout(env: Env, user: Address, layout: DashboardLayout) -> Result<(), Error> {
+        user.require_auth();
+        Self::_check_layout(&layout)?;
+
+        let key = DataKey::UserLayout(user);
+        env.storage().persistent().set(&key, &layout);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    pub fn clear_user_layout(env: Env, user: Address) {
+        user.require_auth();
+        env.storage().persistent().remove(&DataKey::UserLayout(user));
+    }
+
+    // The user's own layout, else their role's layout, else every widget
+    pub fn get_layout(env: Env, viewer: Address) -> Result<DashboardLayout, Error> {
+        if let Some(layout) = env.storage().persistent().get(&DataKey::UserLayout(viewer.clone())) {
+            return Ok(layout);
+        }
+
+        let employee_manager = Self::_employee_manager(&env)?;
+        if let Ok(Ok(role)) = employee_manager.try_get_employee_role(&viewer) {
+            if let Some(layout) = env.storage().persistent().get(&DataKey::RoleLayout(role)) {
+                return Ok(layout);
+            }
+        }
+
+        Ok(DashboardLayout {
+            widgets: Vec::from_array(
+                &env,
+                [
+                    Widget::SalesCount,
+                    Widget::Revenue,
+                    Widget::AverageOrderValue,
+                    Widget::NewCustomers,
+                    Widget::LowStockAlerts,
+                    Widget::PendingOrders,
+                    Widget::OnlineVisitors,
+                ],
+            ),
+            thresholds: Map::new(&env),
+            stores: Vec::new(&env),
+        })
+    }
+
+    // Current values for the viewer's layout; financial widgets need ViewReports.
+    // A store filter narrows the sales widgets; the other widgets stay dashboard-wide.
+    pub fn get_view(env: Env, viewer: Address) -> Result<DashboardView, Error> {
+        viewer.require_auth();
+
+        let layout = Self::get_layout(env.clone(), viewer.clone())?;
+        let can_view_reports = Self::_employee_manager(&env)?.has_permission(&viewer, &Permission::ViewReports);
+        let mut metrics = Self::_real_time_metrics(&env);
+        if !layout.stores.is_empty() {
+            let now = env.ledger().timestamp();
+            let mut sales_count = 0;
+            let mut revenue = 0;
+            for store in layout.stores.iter() {
+                let day = Self::_store_day(&env, store, now);
+                sales_count = Self::_add_u32(sales_count, day.sales_count)?;
+                revenue = Self::_add(revenue, day.revenue)?;
+            }
+            metrics.total_sales_today = sales_count as i128;
+            metrics.total_orders_today = sales_count;
+            metrics.revenue_today = revenue;
+            metrics.average_order_value = if sales_count > 0 { revenue / sales_count as i128 } else { 0 };
+        }
+
+        let mut view = DashboardView {
+            widgets: Vec::new(&env),
+            restricted: Vec::new(&env),
+            stores: layout.stores,
+        };
+        for widget in layout.widgets.iter() {
+            if widget.is_financial() && !can_view_reports {
+                view.restricted.push_back(widget);
+                continue;
+            }
+
+            let value = Self::_widget_value(&metrics, widget);
+            let threshold_breached = match layout.thresholds.get(widget) {
+                Some(threshold) => widget.breaches(value, threshold),
+                None => false,
+            };
+            view.widgets.push_back(WidgetValue {
+                widget,
+                value,
+                threshold_breached,
+            });
+        }
+        Ok(view)
     }
 
     pub fn hello(env: Env) -> String {
         String::from_str(&env, "Hello from Dashboard System!")
     }
-}
\ No newline at end of file
+
+    fn _require_report_viewer(env: &Env, viewer: &Address) -> Result<(), Error> {
+        viewer.require_auth();
+        if Self::_require_admin(env, viewer).is_ok()
+            || Self::_employee_manager(env)?.has_permission(viewer, &Permission::ViewReports)
+        {
+            return Ok(());
+        }
+        Err(Error::from_contract_error(4002)) // Unauthorized
+    }
+
+    fn _real_time_metrics(env: &Env) -> RealTimeMetrics {
+        let now = env.ledger().timestamp();
+        let low_stock_alerts = Self::get_low_stock_count(env.clone());
+        Self::_day_metrics(env, now - now % DAY_IN_SECONDS, low_stock_alerts)
+    }
+
+    fn _daily_snapshot(env: &Env, timestamp: u64) -> Option<RealTimeMetrics> {
+        let day_start = timestamp - timestamp % DAY_IN_SECONDS;
+        if let Some(snapshot) = env.storage().persistent().get(&DataKey::Snapshot(day_start)) {
+            return Some(snapshot);
+        }
+
+        // The latest active day is only frozen when the next day's first event arrives
+        let last_event_day: Option<u64> = env.storage().instance().get(&Symbol::new(env, "LAST_EVENT_DAY"));
+        (last_event_day == Some(day_start))
+            .then(|| Self::_day_metrics(env, day_start, Self::get_low_stock_count(env.clone())))
+    }
+
+    fn _store_day(env: &Env, store: String, timestamp: u64) -> PeriodMetrics {
+        let day_start = timestamp - timestamp % DAY_IN_SECONDS;
+        Self::_load_period(env, &DataKey::StoreDay(store, day_start), day_start)
+    }
+
+    fn _require_admin(env: &Env, admin: &Address) -> Result<(), Error> {
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "ADMIN"))
+            .ok_or(Error::from_contract_error(4002))?;
+        if *admin != stored_admin {
+            return Err(Error::from_contract_error(4002)); // Unauthorized
+        }
+        Ok(())
+    }
+
+    fn _check_layout(layout: &DashboardLayout) -> Result<(), Error> {
+        if layout.stores.len() > MAX_LAYOUT_STORES {
+            return Err(Error::from_contract_error(4010)); // Too many stores
+        }
+        Ok(())
+    }
+
+    fn _delta(env: &Env, current: &RealTimeMetrics, day: u64) -> MetricsDelta {
+        let (sales_count, revenue, average_order_value, new_customers) =
+            match Self::_daily_snapshot(env, day) {
+                Some(previous) => (
+                    previous.total_orders_today as i128,
+                    previous.revenue_today,
+                    previous.average_order_value,
+                    previous.total_customers_today as i128,
+                ),
+                None => (0, 0, 0, 0),
+            };
+
+        MetricsDelta {
+            sales_count: (current.total_orders_today as i128).saturating_sub(sales_count),
+            revenue: current.revenue_today.saturating_sub(revenue),
+            average_order_value: current.average_order_value.saturating_sub(average_order_value),
+            new_customers: (current.total_customers_today as i128).saturating_sub(new_customers),
+        }
+    }
+
+    fn _period_start(day_start: u64, period: TargetPeriod) -> u64 {
+        let days = day_start / DAY_IN_SECONDS;
+        match period {
+            TargetPeriod::Daily => day_start,
+            // 1970-01-01 was a Thursday
+            TargetPeriod::Weekly => day_start - ((days + 3) % 7) * DAY_IN_SECONDS,
+            TargetPeriod::Monthly => day_start - (Self::_day_of_month(days) - 1) * DAY_IN_SECONDS,
+        }
+    }
+
+    // Day of the month (1-31) for a count of days since 1970-01-01,
+    // from Howard Hinnant's civil_from_days
+    fn _day_of_month(days: u64) -> u64 {
+        let z = days + 719_468;
+        let doe = z % 146_097;
+        let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        doy - (153 * mp + 2) / 5 + 1
+    }
+
+    // Today's counters and the given low-stock count as dashboard metrics
+    fn _day_metrics(env: &Env, day_start: u64, low_stock_alerts: u32) -> RealTimeMetrics {
+        let day = Self::_load_period(env, &DataKey::Day(day_start), day_start);
+        let average_order_value = if day.sales_count > 0 {
+            day.revenue / day.sales_count as i128
+        } else {
+            0
+        };
+
+        // Every POS sale is a completed order; nothing on-chain tracks pending orders or visitors
+        RealTimeMetrics {
+            total_sales_today: day.sales_count as i128,
+            total_customers_today: day.new_customers,
+            total_orders_today: day.sales_count,
+            revenue_today: day.revenue,
+            average_order_value,
+            low_stock_alerts,
+            pending_orders: 0,
+            online_visitors: 0,
+        }
+    }
+
+    // The first event of a UTC day freezes the previous active day's closing figures,
+    // while the low-stock count still holds that day's value
+    fn _roll_over(env: &Env, day_start: u64) {
+        let key = Symbol::new(env, "LAST_EVENT_DAY");
+        let last_event_day: Option<u64> = env.storage().instance().get(&key);
+        if last_event_day == Some(day_start) {
+            return;
+        }
+
+        if let Some(last_day) = last_event_day {
+            let snapshot_key = DataKey::Snapshot(last_day);
+            let closing = Self::_day_metrics(env, last_day, Self::get_low_stock_count(env.clone()));
+            env.storage().persistent().set(&snapshot_key, &closing);
+            env.storage().persistent().extend_ttl(&snapshot_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        }
+        env.storage().instance().set(&key, &day_start);
+    }
+
+    fn _save_rule(env: &Env, rule: &AlertRule) {
+        let key = DataKey::Rule(rule.rule_id);
+        env.storage().persistent().set(&key, rule);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    // The event index a rule belongs to; revenue deadlines have none
+    fn _rule_index_key(rule: &AlertRule) -> Option<DataKey> {
+        match &rule.condition {
+            AlertCondition::RevenueBelow(_, _) => None,
+            AlertCondition::StockBelow(sku, _) => Some(DataKey::StockRules(sku.clone())),
+            AlertCondition::RefundRateAbove(_) => Some(DataKey::RefundRules),
+        }
+    }
+
+    fn _rule_index(env: &Env, key: &DataKey) -> Vec<u32> {
+        env.storage().persistent().get(key).unwrap_or(Vec::new(env))
+    }
+
+    fn _index_rule(env: &Env, rule: &AlertRule) -> Result<(), Error> {
+        let Some(key) = Self::_rule_index_key(rule) else {
+            return Ok(());
+        };
+
+        let mut rule_ids = Self::_rule_index(env, &key);
+        if rule_ids.len() >= MAX_RULES_PER_TRIGGER {
+            return Err(Error::from_contract_error(4009)); // Too many rules for this trigger
+        }
+        rule_ids.push_back(rule.rule_id);
+        env.storage().persistent().set(&key, &rule_ids);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    fn _unindex_rule(env: &Env, rule: &AlertRule) {
+        let Some(key) = Self::_rule_index_key(rule) else {
+            return;
+        };
+
+        let mut rule_ids = Self::_rule_index(env, &key);
+        if let Some(index) = rule_ids.first_index_of(rule.rule_id) {
+            rule_ids.remove(index);
+            env.storage().persistent().set(&key, &rule_ids);
+        }
+    }
+
+    fn _evaluate_rules(env: &Env, rule_ids: Vec<u32>) -> Vec<Alert> {
+        let now = env.ledger().timestamp();
+        let day_start = now - now % DAY_IN_SECONDS;
+        let today = Self::_load_period(env, &DataKey::Day(day_start), day_start);
+
+        let mut raised = Vec::new(env);
+        for rule_id in rule_ids.iter() {
+            let Some(mut rule) = env.storage().persistent().get::<_, AlertRule>(&DataKey::Rule(rule_id)) else {
+                continue;
+            };
+            if !rule.active || (rule.last_triggered != 0 && rule.last_triggered >= day_start) {
+                continue;
+            }
+
+            let triggered_value = match &rule.condition {
+                AlertCondition::RevenueBelow(amount, deadline) => {
+                    (now - day_start >= *deadline && today.revenue < *amount).then_some(today.revenue)
+                }
+                AlertCondition::StockBelow(sku, reorder_point) => {
+                    let stock: Option<u32> = env.storage().persistent().get(&DataKey::Stock(sku.clone()));
+                    stock.filter(|stock| stock < reorder_point).map(|stock| stock as i128)
+                }
+                AlertCondition::RefundRateAbove(max_bps) => {
+                    let rate_bps = if today.revenue > 0 {
+                        today.refunded_amount.saturating_mul(10_000) / today.revenue
+                    } else if today.refunded_amount > 0 {
+                        i128::MAX
+                    } else {
+                        0
+                    };
+                    (rate_bps > *max_bps as i128).then_some(rate_bps)
+                }
+            };
+
+            if let Some(value) = triggered_value {
+                rule.last_triggered = now;
+                Self::_save_rule(env, &rule);
+                raised.push_back(Self::_raise_alert(env, &rule, value));
+            }
+        }
+        raised
+    }
+
+    fn _save_alert(env: &Env, alert: &Alert) {
+        let key = DataKey::Alert(alert.alert_id);
+        env.storage().persistent().set(&key, alert);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    fn _open_alert_ids(env: &Env) -> Vec<u32> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, "OPEN_ALERTS"))
+            .unwrap_or(Vec::new(env))
+    }
+
+    fn _raise_alert(env: &Env, rule: &AlertRule, value: i128) -> Alert {
+        let counter_key = Symbol::new(env, "ALERT_COUNTER");
+        let alert_id: u32 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &alert_id);
+
+        let alert = Alert {
+            alert_id,
+            rule_id: rule.rule_id,
+            severity: rule.severity,
+            assignee: rule.assignee.clone(),
+            value,
+            raised_at: env.ledger().timestamp(),
+            status: AlertStatus::Open,
+            acknowledged_by: None,
+            acknowledged_at: 0,
+        };
+        Self::_save_alert(env, &alert);
+        let mut open_alerts = Self::_open_alert_ids(env);
+        open_alerts.push_back(alert_id);
+        env.storage().instance().set(&Symbol::new(env, "OPEN_ALERTS"), &open_alerts);
+
+        // Routed to the assignee, or to this contract when unassigned
+        let subject = rule.assignee.clone().unwrap_or(env.current_contract_address());
+        retail_events::publish(env, retail_events::ALERT_RAISED, &subject, alert.clone());
+        alert
+    }
+
+    fn _employee_manager(env: &Env) -> Result<EmployeeManagerClient<'_>, Error> {
+        let address: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "EMPLOYEE_MANAGER"))
+            .ok_or(Error::from_contract_error(4003))?; // Employee manager not configured
+        Ok(EmployeeManagerClient::new(env, &address))
+    }
+
+    fn _widget_value(metrics: &RealTimeMetrics, widget: Widget) -> i128 {
+        match widget {
+            Widget::SalesCount => metrics.total_orders_today as i128,
+            Widget::Revenue => metrics.revenue_today,
+            Widget::AverageOrderValue => metrics.average_order_value,
+            Widget::NewCustomers => metrics.total_customers_today as i128,
+            Widget::LowStockAlerts => metrics.low_stock_alerts as i128,
+            Widget::PendingOrders => metrics.pending_orders as i128,
+            Widget::OnlineVisitors => metrics.online_visitors as i128,
+        }
+    }
+
+    // Checked counterparts of shared::math, which this crate cannot depend on
+    fn _add(a: i128, b: i128) -> Result<i128, Error> {
+        math::add(a, b).map_err(|_| Error::from_contract_error(4008)) // Overflow
+    }
+
+    fn _add_u32(a: u32, b: u32) -> Result<u32, Error> {
+        math::add_u32(a, b).map_err(|_| Error::from_contract_error(4008)) // Overflow
+    }
+
+    fn _load_period(env: &Env, key: &DataKey, period_start: u64) -> PeriodMetrics {
+        env.storage().persistent().get(key).unwrap_or(PeriodMetrics {
+            period_start,
+            sales_count: 0,
+            revenue: 0,
+            new_customers: 0,
+            refund_count: 0,
+            refunded_amount: 0,
+        })
+    }
+
+    fn _save_period(env: &Env, key: &DataKey, metrics: &PeriodMetrics) {
+        env.storage().persistent().set(key, metrics);
+        env.storage().persistent().extend_ttl(key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    // Keeps LOW_STOCK_COUNT in step with the set of SKUs at or below the threshold
+    fn _update_low_stock(env: &Env, sku: String, quantity: u32) -> Result<(), Error> {
+        let stock_key = DataKey::Stock(sku.clone());
+        env.storage().persistent().set(&stock_key, &quantity);
+        env.storage().persistent().extend_ttl(&stock_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+
+        let key = DataKey::LowStock(sku);
+        let was_low = env.storage().persistent().has(&key);
+        let is_low = quantity <= Self::get_low_stock_threshold(env.clone());
+        if was_low == is_low {
+            return Ok(());
+        }
+
+        let count = Self::get_low_stock_count(env.clone());
+        if is_low {
+            env.storage().persistent().set(&key, &true);
+            env.storage().instance().set(&Symbol::new(env, "LOW_STOCK_COUNT"), &Self::_add_u32(count, 1)?);
+        } else {
+            env.storage().persistent().remove(&key);
+            env.storage().instance().set(&Symbol::new(env, "LOW_STOCK_COUNT"), &count.saturating_sub(1));
+        }
+        Ok(())
+    }
+}
+
+mod test;
\ No newline at end of file
@@ -0,0 +1,328 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{testutils::{Address as _, Ledger}, Env};
+
+fn setup(env: &Env) -> (DashboardClient<'_>, Address, DataSources) {
+    let admin = Address::generate(env);
+    let contract_id = env.register_contract(None, Dashboard);
+    let client = DashboardClient::new(env, &contract_id);
+    client.initialize(&admin);
+
+    let sources = DataSources {
+        pos_system: Address::generate(env),
+        inventory_manager: Address::generate(env),
+        crm_system: Address::generate(env),
+        payment_processor: Address::generate(env),
+    };
+    client.set_data_sources(&admin, &sources);
+    (client, admin, sources)
+}
+
+#[test]
+fn test_record_event_updates_counters() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86_400 + 3_600);
+    let (client, admin, sources) = setup(&env);
+    assert!(client.try_initialize(&Address::generate(&env)).is_err());
+
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(600_000));
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(400_000));
+    client.record_event(&sources.crm_system, &MetricEvent::CustomerRegistered);
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 5));
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU2"), 3));
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU2"), 50));
+
+    let metrics = client.get_real_time_metrics(&admin);
+    assert_eq!(metrics.total_orders_today, 2);
+    assert_eq!(metrics.revenue_today, 1_000_000);
+    assert_eq!(metrics.average_order_value, 500_000);
+    assert_eq!(metrics.total_customers_today, 1);
+    assert_eq!(metrics.low_stock_alerts, 1);
+
+    let hours = client.get_hourly_metrics(&admin, &86_400);
+    assert_eq!(hours.len(), 24);
+    assert_eq!(hours.get(1).unwrap().sales_count, 2);
+    assert_eq!(hours.get(0).unwrap().sales_count, 0);
+
+    // The next day starts from zero while yesterday stays queryable
+    env.ledger().with_mut(|li| li.timestamp = 2 * 86_400);
+    assert_eq!(client.get_real_time_metrics(&admin).revenue_today, 0);
+    assert_eq!(client.get_daily_metrics(&admin, &86_400).revenue, 1_000_000);
+}
+
+#[test]
+fn test_record_event_rejects_wrong_source() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, _, sources) = setup(&env);
+
+    assert!(client.try_record_event(&sources.crm_system, &MetricEvent::Sale(100)).is_err());
+    assert!(client.try_record_event(&Address::generate(&env), &MetricEvent::CustomerRegistered).is_err());
+}
+
+// Stand-in for employee_manager: the accountant can view reports, the cashier cannot
+#[contract]
+struct MockEmployeeManager;
+
+#[contractimpl]
+impl MockEmployeeManager {
+    pub fn set_staff(env: Env, accountant: Address, cashier: Address) {
+        env.storage().instance().set(&Symbol::new(&env, "ACCOUNTANT"), &accountant);
+        env.storage().instance().set(&Symbol::new(&env, "CASHIER"), &cashier);
+    }
+
+    pub fn has_permission(env: Env, employee_address: Address, required_permission: Permission) -> bool {
+        let accountant: Address = env.storage().instance().get(&Symbol::new(&env, "ACCOUNTANT")).unwrap();
+        employee_address == accountant && required_permission == Permission::ViewReports
+    }
+
+    pub fn get_employee_role(env: Env, wallet_address: Address) -> EmployeeRole {
+        let accountant: Address = env.storage().instance().get(&Symbol::new(&env, "ACCOUNTANT")).unwrap();
+        if wallet_address == accountant {
+            EmployeeRole::Accountant
+        } else {
+            EmployeeRole::Cashier
+        }
+    }
+}
+
+fn layout_with_stores(env: &Env) -> DashboardLayout {
+    DashboardLayout {
+        widgets: Vec::from_array(env, [Widget::Revenue, Widget::SalesCount]),
+        thresholds: Map::new(env),
+        stores: Vec::from_array(env, [String::from_str(env, "HCM-01")]),
+    }
+}
+
+#[test]
+fn test_views_hide_financial_widgets_without_view_reports() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, sources) = setup(&env);
+
+    let accountant = Address::generate(&env);
+    let cashier = Address::generate(&env);
+    let employee_manager = env.register_contract(None, MockEmployeeManager);
+    MockEmployeeManagerClient::new(&env, &employee_manager).set_staff(&accountant, &cashier);
+    client.set_employee_manager(&admin, &employee_manager);
+
+    // Two store tills next to the shared POS contract
+    let hcm_pos = Address::generate(&env);
+    let hn_pos = Address::generate(&env);
+    client.set_store_pos(&admin, &hcm_pos, &String::from_str(&env, "HCM-01"));
+    client.set_store_pos(&admin, &hn_pos, &String::from_str(&env, "HN-01"));
+    client.record_event(&hcm_pos, &MetricEvent::Sale(300));
+    client.record_event(&hcm_pos, &MetricEvent::Sale(100));
+    client.record_event(&hn_pos, &MetricEvent::Sale(900));
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(50));
+    assert_eq!(client.get_real_time_metrics(&admin).revenue_today, 1_350);
+
+    let mut thresholds = Map::new(&env);
+    thresholds.set(Widget::Revenue, 1_000);
+    let layout = DashboardLayout {
+        thresholds,
+        ..layout_with_stores(&env)
+    };
+    client.set_role_layout(&admin, &EmployeeRole::Accountant, &layout.clone());
+    client.set_role_layout(&admin, &EmployeeRole::Cashier, &layout);
+
+    let view = client.get_view(&accountant);
+    assert_eq!(view.widgets.len(), 2);
+    let revenue = view.widgets.get(0).unwrap();
+    assert_eq!(revenue.value, 400);
+    assert!(revenue.threshold_breached);
+    assert_eq!(view.stores.len(), 1);
+
+    let view = client.get_view(&cashier);
+    assert_eq!(view.widgets.len(), 1);
+    assert_eq!(view.widgets.get(0).unwrap().widget, Widget::SalesCount);
+    assert_eq!(view.widgets.get(0).unwrap().value, 2);
+    assert_eq!(view.restricted, Vec::from_array(&env, [Widget::Revenue]));
+
+    // The raw metrics getters apply the same ViewReports check
+    let hcm = String::from_str(&env, "HCM-01");
+    assert_eq!(client.get_store_daily_metrics(&accountant, &hcm, &0).revenue, 400);
+    assert!(client.try_get_store_daily_metrics(&cashier, &hcm, &0).is_err());
+    assert!(client.try_get_real_time_metrics(&cashier).is_err());
+    assert!(client.try_get_daily_metrics(&cashier, &0).is_err());
+    assert!(client.try_get_hourly_metrics(&cashier, &0).is_err());
+    assert!(client.try_get_metrics_comparison(&cashier, &hcm).is_err());
+    assert!(client.try_get_daily_snapshot(&cashier, &0).is_err());
+
+    let mut too_many_stores = layout_with_stores(&env);
+    for _ in 0..MAX_LAYOUT_STORES {
+        too_many_stores.stores.push_back(String::from_str(&env, "HCM-01"));
+    }
+    assert!(client.try_set_user_layout(&cashier, &too_many_stores).is_err());
+
+    // A personal layout wins over the role layout
+    client.set_user_layout(&cashier, &DashboardLayout {
+        widgets: Vec::from_array(&env, [Widget::NewCustomers]),
+        thresholds: Map::new(&env),
+        stores: Vec::new(&env),
+    });
+    assert_eq!(client.get_view(&cashier).widgets.get(0).unwrap().widget, Widget::NewCustomers);
+}
+
+#[test]
+fn test_alert_rules_raise_and_acknowledge() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86_400 + 9 * 3_600);
+    let (client, admin, sources) = setup(&env);
+    let manager = Address::generate(&env);
+
+    let revenue_rule = client.add_alert_rule(
+        &admin,
+        &AlertCondition::RevenueBelow(1_000, 14 * 3_600),
+        &AlertSeverity::Warning,
+        &Some(manager.clone()),
+    );
+    client.add_alert_rule(
+        &admin,
+        &AlertCondition::StockBelow(String::from_str(&env, "SKU1"), 10),
+        &AlertSeverity::Critical,
+        &None,
+    );
+    client.add_alert_rule(&admin, &AlertCondition::RefundRateAbove(1_000), &AlertSeverity::Info, &None);
+
+    // 500 revenue, 100 refunded (20%), stock still fine
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(500));
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 12));
+    client.record_event(&sources.payment_processor, &MetricEvent::Refund(100));
+    let open = client.get_open_alerts();
+    assert_eq!(open.len(), 1);
+    assert_eq!(open.get(0).unwrap().value, 2_000);
+
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 4));
+    assert_eq!(client.get_open_alerts().len(), 2);
+    // Rules fire once per day
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 3));
+    assert_eq!(client.get_open_alerts().len(), 2);
+
+    // Revenue is still short at 14:00
+    env.ledger().with_mut(|li| li.timestamp = 86_400 + 14 * 3_600);
+    let raised = client.check_alerts();
+    assert_eq!(raised.len(), 1);
+    let alert = raised.get(0).unwrap();
+    assert_eq!(alert.rule_id, revenue_rule);
+    assert_eq!(alert.assignee, Some(manager.clone()));
+
+    assert!(client.try_acknowledge_alert(&Address::generate(&env), &alert.alert_id).is_err());
+    let acknowledged = client.acknowledge_alert(&manager, &alert.alert_id);
+    assert_eq!(acknowledged.status, AlertStatus::Acknowledged);
+    assert!(client.try_acknowledge_alert(&manager, &alert.alert_id).is_err());
+    assert_eq!(client.get_open_alerts().len(), 2);
+}
+
+#[test]
+fn test_comparisons_and_targets() {
+    let env = Env::default();
+    env.mock_all_auths();
+    let (client, admin, sources) = setup(&env);
+    let store = String::from_str(&env, "HCM-01");
+    let store_pos = Address::generate(&env);
+    client.set_store_pos(&admin, &store_pos, &store);
+
+    // 2024-01-08 is a Monday; the Monday before gets 300, the Sunday 500
+    let monday = 1_704_672_000;
+    env.ledger().with_mut(|li| li.timestamp = monday - 7 * 86_400 + 100);
+    client.record_event(&store_pos, &MetricEvent::Sale(300));
+    env.ledger().with_mut(|li| li.timestamp = monday - 86_400 + 100);
+    client.record_event(&store_pos, &MetricEvent::Sale(500));
+    env.ledger().with_mut(|li| li.timestamp = monday + 100);
+    client.record_event(&store_pos, &MetricEvent::Sale(400));
+    client.record_event(&sources.crm_system, &MetricEvent::CustomerRegistered);
+    // Sales outside the store count dashboard-wide but not towards its targets
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(1_000));
+
+    assert_eq!(client.get_daily_snapshot(&admin, &(monday - 86_400)).unwrap().revenue_today, 500);
+    assert!(client.try_set_sales_target(&admin, &store, &TargetPeriod::Daily, &0).is_err());
+    client.set_sales_target(&admin, &store, &TargetPeriod::Daily, &800);
+    client.set_sales_target(&admin, &store, &TargetPeriod::Weekly, &4_000);
+    client.set_sales_target(&admin, &store, &TargetPeriod::Monthly, &2_400);
+
+    let comparison = client.get_metrics_comparison(&admin, &store);
+    assert_eq!(comparison.current.revenue_today, 1_400);
+    assert_eq!(comparison.vs_yesterday.revenue, 900);
+    assert_eq!(comparison.vs_last_week.revenue, 1_100);
+    assert_eq!(comparison.vs_yesterday.new_customers, 1);
+
+    let daily = comparison.targets.get(0).unwrap();
+    assert_eq!(daily.achieved, 400);
+    assert_eq!(daily.attainment_bps, 5_000);
+    // The week started today
+    let weekly = comparison.targets.get(1).unwrap();
+    assert_eq!(weekly.period_start, monday);
+    assert_eq!(weekly.achieved, 400);
+    // January so far holds all three sales
+    let monthly = comparison.targets.get(2).unwrap();
+    assert_eq!(monthly.period_start, 1_704_067_200);
+    assert_eq!(monthly.achieved, 1_200);
+    assert_eq!(monthly.attainment_bps, 5_000);
+
+    // Attainment saturates instead of overflowing on a huge day
+    client.record_event(&store_pos, &MetricEvent::Sale(i128::MAX / 1_000));
+    client.set_sales_target(&admin, &store, &TargetPeriod::Daily, &1);
+    let daily = client.get_metrics_comparison(&admin, &store).targets.get(0).unwrap();
+    assert_eq!(daily.attainment_bps, i128::MAX);
+}
+
+#[test]
+fn test_events_only_run_their_indexed_rules() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86_400 + 15 * 3_600);
+    let (client, admin, sources) = setup(&env);
+    let sku = String::from_str(&env, "SKU1");
+
+    // Past its deadline, but revenue rules wait for check_alerts
+    client.add_alert_rule(&admin, &AlertCondition::RevenueBelow(1_000, 14 * 3_600), &AlertSeverity::Warning, &None);
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(500));
+    assert_eq!(client.get_open_alerts().len(), 0);
+    assert_eq!(client.check_alerts().len(), 1);
+
+    let mut stock_rules = Vec::new(&env);
+    for reorder_point in 1..=MAX_RULES_PER_TRIGGER {
+        stock_rules.push_back(client.add_alert_rule(
+            &admin,
+            &AlertCondition::StockBelow(sku.clone(), reorder_point),
+            &AlertSeverity::Info,
+            &None,
+        ));
+    }
+    let sixth = client.try_add_alert_rule(&admin, &AlertCondition::StockBelow(sku.clone(), 10), &AlertSeverity::Info, &None);
+    assert!(sixth.is_err());
+
+    // A deactivated rule frees its slot and stays quiet
+    client.set_alert_rule_active(&admin, &stock_rules.get(0).unwrap(), &false);
+    client.add_alert_rule(&admin, &AlertCondition::StockBelow(sku.clone(), 10), &AlertSeverity::Info, &None);
+    assert!(client.try_set_alert_rule_active(&admin, &stock_rules.get(0).unwrap(), &true).is_err());
+
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(sku.clone(), 0));
+    assert_eq!(client.get_open_alerts().len(), 1 + MAX_RULES_PER_TRIGGER);
+}
+
+#[test]
+fn test_snapshot_freezes_on_next_day() {
+    let env = Env::default();
+    env.mock_all_auths();
+    env.ledger().with_mut(|li| li.timestamp = 86_400 + 100);
+    let (client, admin, sources) = setup(&env);
+    client.set_low_stock_threshold(&admin, &5);
+
+    client.record_event(&sources.pos_system, &MetricEvent::Sale(700));
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 2));
+    // Until the next day's first event the snapshot follows the live counters
+    assert_eq!(client.get_daily_snapshot(&admin, &86_400).unwrap().revenue_today, 700);
+    assert!(client.get_daily_snapshot(&admin, &(2 * 86_400)).is_none());
+
+    env.ledger().with_mut(|li| li.timestamp = 2 * 86_400 + 100);
+    client.record_event(&sources.inventory_manager, &MetricEvent::StockChanged(String::from_str(&env, "SKU1"), 50));
+    let closing = client.get_daily_snapshot(&admin, &86_400).unwrap();
+    assert_eq!(closing.revenue_today, 700);
+    assert_eq!(closing.low_stock_alerts, 1);
+    assert_eq!(client.get_daily_snapshot(&admin, &(2 * 86_400)).unwrap().low_stock_alerts, 0);
+}
@@ -70,18 +70,18 @@ pub enum Permission {
     IssueLoyaltyPoints,
     ProcessPayroll,
     SystemAdmin,
+    FulfillRedemptions,
 }
 
+// Department, status and role changes go through reassign_employee: soroban-sdk 21
+// cannot encode an optional enum field inside a contracttype struct
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub struct EmployeeUpdate {
     pub name: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
-    pub department: Option<Department>,
     pub position: Option<String>,
-    pub status: Option<EmployeeStatus>,
-    pub role: Option<EmployeeRole>,
     pub salary_grade: Option<u32>,
 }
 
@@ -150,6 +150,7 @@ impl EmployeeManager {
                 Permission::IssueLoyaltyPoints,
                 Permission::ProcessPayroll,
                 Permission::SystemAdmin,
+                Permission::FulfillRedemptions,
             ])
         );
         
@@ -162,6 +163,7 @@ impl EmployeeManager {
                 Permission::ManageInventory,
                 Permission::ViewReports,
                 Permission::ManageVendors,
+                Permission::FulfillRedemptions,
             ])
         );
         
@@ -171,6 +173,7 @@ impl EmployeeManager {
             Vec::from_array(&env, [
                 Permission::ProcessPayments,
                 Permission::IssueLoyaltyPoints,
+                Permission::FulfillRedemptions,
             ])
         );
         
@@ -185,6 +188,7 @@ impl EmployeeManager {
         env.storage().instance().set(&Symbol::new(&env, "ROLE_PERMISSIONS"), &role_permissions);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_employee(
         env: Env,
         admin: Address,
@@ -272,16 +276,46 @@ impl EmployeeManager {
         if let Some(phone) = updates.phone {
             employee.phone = phone;
         }
-        if let Some(department) = updates.department {
-            employee.department = department;
-        }
         if let Some(position) = updates.position {
             employee.position = position;
         }
-        if let Some(status) = updates.status {
+        if let Some(salary_grade) = updates.salary_grade {
+            employee.salary_grade = salary_grade;
+        }
+
+        employees.set(employee_id, employee.clone());
+        env.storage().instance().set(&employees_key, &employees);
+
+        Ok(employee)
+    }
+
+    pub fn reassign_employee(
+        env: Env,
+        admin: Address,
+        employee_id: String,
+        department: Option<Department>,
+        status: Option<EmployeeStatus>,
+        role: Option<EmployeeRole>,
+    ) -> Result<Employee, EmployeeError> {
+        admin.require_auth();
+        Self::check_permission(env.clone(), admin.clone(), Permission::ManageEmployees)?;
+
+        let employees_key = Symbol::new(&env, "EMPLOYEES");
+        let mut employees: Map<String, Employee> = env
+            .storage()
+            .instance()
+            .get(&employees_key)
+            .unwrap_or(Map::new(&env));
+
+        let mut employee = employees.get(employee_id.clone()).ok_or(EmployeeError::EmployeeNotFound)?;
+
+        if let Some(department) = department {
+            employee.department = department;
+        }
+        if let Some(status) = status {
             employee.status = status;
         }
-        if let Some(role) = updates.role {
+        if let Some(role) = role {
             employee.role = role;
             // Update permissions when role changes
             let role_permissions: Map<EmployeeRole, Vec<Permission>> = env
@@ -291,9 +325,6 @@ impl EmployeeManager {
                 .unwrap_or(Map::new(&env));
             employee.permissions = role_permissions.get(role).unwrap_or(Vec::new(&env));
         }
-        if let Some(salary_grade) = updates.salary_grade {
-            employee.salary_grade = salary_grade;
-        }
 
         employees.set(employee_id, employee.clone());
         env.storage().instance().set(&employees_key, &employees);
@@ -351,6 +382,10 @@ impl EmployeeManager {
         Err(EmployeeError::EmployeeNotFound)
     }
 
+    pub fn get_employee_role(env: Env, wallet_address: Address) -> Result<EmployeeRole, EmployeeError> {
+        Ok(Self::get_employee_by_wallet(env, wallet_address)?.role)
+    }
+
     pub fn get_employees_by_department(env: Env, department: Department) -> Vec<Employee> {
         let employees: Map<String, Employee> = env
             .storage()
@@ -3,6 +3,8 @@ use soroban_sdk::{
     contract, contractimpl, contracttype,
     Env, Symbol, String, Address, Vec, 
 };
+use retail_events::{ProductAddedEvent, StockUpdatedEvent};
+use shared::{DashboardClient, MetricEvent};
 
 #[contract]
 pub struct InventoryManager;
@@ -56,10 +58,8 @@ impl TryFrom<soroban_sdk::Error> for RetailError {
     type Error = soroban_sdk::Error;
     
     fn try_from(err: soroban_sdk::Error) -> Result<Self, Self::Error> {
-        // Map specific error codes to RetailError
-        match err {
-            _ => Err(err), // Hoặc custom mapping nếu cần
-        }
+        // Map specific error codes to RetailError here if needed
+        Err(err)
     }
 }
 
@@ -69,6 +69,70 @@ impl InventoryManager {
         env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
     }
 
+    // Stock levels are reported to this dashboard as they change
+    pub fn set_dashboard(env: Env, admin: Address, dashboard: Address) -> Result<(), RetailError> {
+        admin.require_auth();
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "ADMIN"))
+            .ok_or(RetailError::Unauthorized)?;
+        if admin != stored_admin {
+            return Err(RetailError::Unauthorized);
+        }
+
+        env.storage().instance().set(&Symbol::new(&env, "DASHBOARD"), &dashboard);
+        // A new dashboard needs every existing product reported again
+        env.storage().instance().remove(&Symbol::new(&env, "DASHBOARD_SYNC_CURSOR"));
+        Ok(())
+    }
+
+    pub fn get_dashboard(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Symbol::new(&env, "DASHBOARD"))
+    }
+
+    // Reports the stock of products added before the dashboard was set, `limit` at a
+    // time; returns how many products are still to be reported
+    pub fn sync_dashboard(env: Env, admin: Address, limit: u32) -> Result<u32, RetailError> {
+        admin.require_auth();
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "ADMIN"))
+            .ok_or(RetailError::Unauthorized)?;
+        if admin != stored_admin {
+            return Err(RetailError::Unauthorized);
+        }
+
+        let Some(dashboard) = Self::get_dashboard(env.clone()) else {
+            return Ok(0);
+        };
+        let products = Self::get_all_products(env.clone());
+        let cursor_key = Symbol::new(&env, "DASHBOARD_SYNC_CURSOR");
+        let cursor: u32 = env.storage().instance().get(&cursor_key).unwrap_or(0);
+
+        let end = products.len().min(cursor.saturating_add(limit));
+        for i in cursor..end {
+            let product = products.get(i).unwrap();
+            DashboardClient::new(&env, &dashboard).record_event(
+                &env.current_contract_address(),
+                &MetricEvent::StockChanged(product.sku, product.stock_quantity),
+            );
+        }
+        env.storage().instance().set(&cursor_key, &end);
+
+        Ok(products.len() - end)
+    }
+
+    // A failing dashboard must not block the stock change
+    fn _report_stock(env: &Env, sku: String, quantity: u32) {
+        if let Some(dashboard) = Self::get_dashboard(env.clone()) {
+            let _ = DashboardClient::new(env, &dashboard)
+                .try_record_event(&env.current_contract_address(), &MetricEvent::StockChanged(sku, quantity));
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn add_product(
         env: Env,
         admin: Address,
@@ -112,11 +176,22 @@ impl InventoryManager {
         env.storage().instance().set(&products_key, &products);
 
         // ✅ EVENT: Product Added - dùng supplier_clone
-        env.events().publish(
-            (Symbol::new(&env, "product_added"),),
-            (sku.clone(), name.clone(), price, initial_stock, supplier_clone, location)
+        retail_events::publish(
+            &env,
+            retail_events::PRODUCT_ADDED,
+            &admin,
+            ProductAddedEvent {
+                sku: sku.clone(),
+                name: name.clone(),
+                price,
+                initial_stock,
+                supplier: supplier_clone,
+                location,
+            },
         );
 
+        Self::_report_stock(&env, sku, initial_stock);
+
         Ok(product)
     }
 
@@ -160,11 +235,15 @@ impl InventoryManager {
             env.storage().instance().set(&products_key, &products);
 
             // ✅ EVENT: Stock Updated
-            env.events().publish(
-                (Symbol::new(&env, "stock_updated"),),
-                (sku.clone(), new_quantity)
+            retail_events::publish(
+                &env,
+                retail_events::STOCK_UPDATED,
+                &admin,
+                StockUpdatedEvent { sku: sku.clone(), new_quantity },
             );
 
+            Self::_report_stock(&env, sku, new_quantity);
+
             Ok(updated_product)
         } else {
             Err(RetailError::ProductNotFound)
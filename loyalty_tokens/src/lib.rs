@@ -1,46 +1,1361 @@
 ﻿#![no_std]
-use soroban_sdk::{contract, contractimpl, Address, Env, Symbol};
+use soroban_sdk::{contract, contractimpl, contracttype, Address, Env, Map, String, Symbol, Vec};
+use retail_events::{IssuerUpdatedEvent, PointsMovedEvent, TransferBlockedEvent};
+use shared::{math, CrmClient, CustomerTier, EmployeeManagerClient, Permission, RetailError};
 
 #[contract]
 pub struct LoyaltyToken;
 
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PointsTxKind {
+    Earn,
+    Redeem,
+    Expire,
+    Adjust,
+    TransferIn,
+    TransferOut,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointsTransaction {
+    pub tx_id: u64,
+    pub kind: PointsTxKind,
+    pub amount: i128, // signed change applied to the balance
+    pub balance_after: i128,
+    pub reason: String,
+    pub reference_id: String, // e.g. the sale or order id that caused the movement
+    pub counterparty: Option<Address>, // other side of a transfer or pooled redemption
+    pub timestamp: u64,
+}
+
+// Points earned together; consumed oldest-first and expired as a whole
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointLot {
+    pub amount: i128, // remaining in this lot
+    pub earned_at: u64,
+    pub expires_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExpiringPoints {
+    pub account: Address,
+    pub amount: i128,
+    pub next_expiry: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IssuerKind {
+    Contract, // e.g. the pos_system contract
+    Cashier,  // must also hold Permission::IssueLoyaltyPoints in employee_manager
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Issuer {
+    pub address: Address,
+    pub kind: IssuerKind,
+    pub daily_cap: i128,
+    pub added_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferPolicy {
+    pub enabled: bool,
+    pub fee_bps: u32,      // charged to the sender on top of the amount and burned
+    pub min_amount: i128,
+    pub max_amount: i128,  // per transfer, 0 = no cap
+    pub daily_limit: i128, // per sender, 0 = no cap
+}
+
+// Members pool their points; only the primary account can spend the pool
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Household {
+    pub household_id: u32,
+    pub primary: Address,
+    pub members: Vec<Address>, // primary first
+    pub created_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reward {
+    pub reward_id: u32,
+    pub name: String,
+    pub points_cost: i128,
+    pub stock: u32,
+    pub valid_from: u64,
+    pub valid_until: u64, // 0 = no end date
+    pub min_tier: CustomerTier, // Bronze = open to everyone
+    pub active: bool,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RedemptionStatus {
+    Pending,
+    Fulfilled,
+    Cancelled, // points refunded and stock released
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct RedemptionOrder {
+    pub order_id: u32,
+    pub reward_id: u32,
+    pub customer: Address,
+    pub points_cost: i128,
+    pub claim_code: String, // shown by the customer at the counter
+    pub redeemed_lots: Vec<PointLot>, // given back with their original expiry on cancel
+    pub status: RedemptionStatus,
+    pub created_at: u64,
+    pub fulfilled_at: Option<u64>,
+    pub fulfilled_by: Option<Address>,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum DataKey {
+    Balance(Address),
+    History(Address, u32), // account, position in the account's history
+    HistoryCount(Address),
+    Lots(Address),
+    IssuedOnDay(Address, u64), // issuer, day number since epoch
+    TransferredOnDay(Address, u64),
+    TransferBlocked(Address),
+    Household(u32),
+    HouseholdOf(Address),
+    Redemption(u32),
+    CustomerRedemptions(Address),
+    Holder(u32), // position in first-seen order
+    HolderIndex(Address),
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LoyaltyError {
+    Unauthorized = 0,
+    NotInitialized = 1,
+    AlreadyInitialized = 2,
+    InvalidAmount = 3,
+    InsufficientPoints = 4,
+    Overflow = 5,
+    IssuerNotFound = 6,
+    DailyCapExceeded = 7,
+    EmployeeManagerNotSet = 8,
+    TransfersDisabled = 9,
+    TransferLimitExceeded = 10,
+    HouseholdNotFound = 11,
+    AlreadyInHousehold = 12,
+    HouseholdFull = 13,
+    RewardNotFound = 14,
+    RewardUnavailable = 15,
+    TierRestricted = 16,
+    CrmNotSet = 17,
+    RedemptionNotFound = 18,
+    InvalidClaimCode = 19,
+    AlreadyFulfilled = 20,
+    RedemptionCancelled = 21,
+}
+
+impl From<LoyaltyError> for soroban_sdk::Error {
+    fn from(e: LoyaltyError) -> soroban_sdk::Error {
+        match e {
+            LoyaltyError::Unauthorized => soroban_sdk::Error::from_contract_error(0),
+            LoyaltyError::NotInitialized => soroban_sdk::Error::from_contract_error(1),
+            LoyaltyError::AlreadyInitialized => soroban_sdk::Error::from_contract_error(2),
+            LoyaltyError::InvalidAmount => soroban_sdk::Error::from_contract_error(3),
+            LoyaltyError::InsufficientPoints => soroban_sdk::Error::from_contract_error(4),
+            LoyaltyError::Overflow => soroban_sdk::Error::from_contract_error(5),
+            LoyaltyError::IssuerNotFound => soroban_sdk::Error::from_contract_error(6),
+            LoyaltyError::DailyCapExceeded => soroban_sdk::Error::from_contract_error(7),
+            LoyaltyError::EmployeeManagerNotSet => soroban_sdk::Error::from_contract_error(8),
+            LoyaltyError::TransfersDisabled => soroban_sdk::Error::from_contract_error(9),
+            LoyaltyError::TransferLimitExceeded => soroban_sdk::Error::from_contract_error(10),
+            LoyaltyError::HouseholdNotFound => soroban_sdk::Error::from_contract_error(11),
+            LoyaltyError::AlreadyInHousehold => soroban_sdk::Error::from_contract_error(12),
+            LoyaltyError::HouseholdFull => soroban_sdk::Error::from_contract_error(13),
+            LoyaltyError::RewardNotFound => soroban_sdk::Error::from_contract_error(14),
+            LoyaltyError::RewardUnavailable => soroban_sdk::Error::from_contract_error(15),
+            LoyaltyError::TierRestricted => soroban_sdk::Error::from_contract_error(16),
+            LoyaltyError::CrmNotSet => soroban_sdk::Error::from_contract_error(17),
+            LoyaltyError::RedemptionNotFound => soroban_sdk::Error::from_contract_error(18),
+            LoyaltyError::InvalidClaimCode => soroban_sdk::Error::from_contract_error(19),
+            LoyaltyError::AlreadyFulfilled => soroban_sdk::Error::from_contract_error(20),
+            LoyaltyError::RedemptionCancelled => soroban_sdk::Error::from_contract_error(21),
+        }
+    }
+}
+
+impl From<&LoyaltyError> for soroban_sdk::Error {
+    fn from(e: &LoyaltyError) -> soroban_sdk::Error {
+        (*e).into()
+    }
+}
+
+impl TryFrom<soroban_sdk::Error> for LoyaltyError {
+    type Error = soroban_sdk::Error;
+    fn try_from(_err: soroban_sdk::Error) -> Result<Self, Self::Error> {
+        Err(soroban_sdk::Error::from_contract_error(999))
+    }
+}
+
+// Lets shared::math results propagate with `?`
+impl From<RetailError> for LoyaltyError {
+    fn from(e: RetailError) -> LoyaltyError {
+        match e {
+            RetailError::Overflow => LoyaltyError::Overflow,
+            _ => LoyaltyError::InvalidAmount,
+        }
+    }
+}
+
+// Balance and history entries are bumped to ~30 days whenever they drop below ~29 days of TTL
+const DAY_IN_LEDGERS: u32 = 17_280;
+const BUMP_AMOUNT: u32 = 30 * DAY_IN_LEDGERS;
+const LIFETIME_THRESHOLD: u32 = BUMP_AMOUNT - DAY_IN_LEDGERS;
+
+// Points expire 12 months after they are earned unless the admin configures otherwise
+const DEFAULT_EXPIRY_PERIOD: u64 = 365 * 86_400;
+
+const MAX_HOUSEHOLD_MEMBERS: u32 = 8;
+
 #[contractimpl]
 impl LoyaltyToken {
-    pub fn init(env: Env, admin: Address) {
+    pub fn init(env: Env, admin: Address) -> Result<(), LoyaltyError> {
         // Yêu cầu người gọi phải là admin để khởi tạo
         admin.require_auth();
         let key = Symbol::new(&env, "admin");
+        if env.storage().persistent().has(&key) {
+            return Err(LoyaltyError::AlreadyInitialized);
+        }
         env.storage().persistent().set(&key, &admin);
+        Ok(())
     }
 
-    pub fn issue_points(env: Env, to: Address, amount: i128) {
-        let key = Symbol::new(&env, "admin");
-        // Lấy admin từ persistent storage, bắt buộc phải có admin đã được set
-        let admin: Address = env
-            .storage()
-            .persistent()
-            .get::<Symbol, Address>(&key)
-            .expect("Admin chưa được khởi tạo");
-        // Yêu cầu admin phải xác thực
-        admin.require_auth();
+    // The admin issues without limits; registered issuers are held to their daily cap
+    pub fn issue_points(
+        env: Env,
+        issuer: Address,
+        to: Address,
+        amount: i128,
+        reason: String,
+        reference_id: String,
+    ) -> Result<PointsTransaction, LoyaltyError> {
+        // Yêu cầu issuer phải xác thực
+        issuer.require_auth();
+
+        if amount <= 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+        if issuer != Self::_admin(&env)? {
+            Self::_charge_issuer(&env, &issuer, amount)?;
+        }
 
-        // Lấy điểm hiện tại của người nhận, cộng thêm amount mới
-        let mut balance = env.storage().persistent().get::<Address, i128>(&to).unwrap_or(0);
-        balance += amount;
-        env.storage().persistent().set(&to, &balance);
+        // Cộng thêm amount mới vào điểm hiện tại của người nhận
+        Self::_add_lot(&env, &to, amount);
+        Self::_record(&env, &to, PointsTxKind::Earn, amount, reason, reference_id, None)
     }
 
     pub fn get_balance(env: Env, owner: Address) -> i128 {
-        env.storage().persistent().get::<Address, i128>(&owner).unwrap_or(0)
+        Self::_read_balance(&env, &owner)
     }
 
-    pub fn redeem_points(env: Env, from: Address, amount: i128) {
+    pub fn redeem_points(
+        env: Env,
+        from: Address,
+        amount: i128,
+        reason: String,
+        reference_id: String,
+    ) -> Result<PointsTransaction, LoyaltyError> {
         // Yêu cầu người dùng xác thực
         from.require_auth();
 
-        let mut balance = env.storage().persistent().get::<Address, i128>(&from).unwrap_or(0);
-        assert!(balance >= amount, "Not enough points");
-        balance -= amount;
-        env.storage().persistent().set(&from, &balance);
+        if amount <= 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        // Expired lots must not pay for anything
+        Self::_expire_lots(&env, &from)?;
+
+        Self::_consume_lots(&env, &from, amount)?;
+        Self::_record(&env, &from, PointsTxKind::Redeem, -amount, reason, reference_id, None)
+    }
+
+    // Admin correction in either direction; a balance can never go below zero
+    pub fn adjust_points(
+        env: Env,
+        admin: Address,
+        account: Address,
+        delta: i128,
+        reason: String,
+        reference_id: String,
+    ) -> Result<PointsTransaction, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        if delta == 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        Self::_expire_lots(&env, &account)?;
+
+        if delta > 0 {
+            Self::_add_lot(&env, &account, delta);
+        } else {
+            Self::_consume_lots(&env, &account, math::sub(0, delta)?)?;
+        }
+        Self::_record(&env, &account, PointsTxKind::Adjust, delta, reason, reference_id, None)
+    }
+
+    pub fn add_issuer(
+        env: Env,
+        admin: Address,
+        issuer: Address,
+        kind: IssuerKind,
+        daily_cap: i128,
+    ) -> Result<Issuer, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        if daily_cap <= 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        // Adding an existing issuer again updates its kind and cap
+        let entry = Issuer {
+            address: issuer.clone(),
+            kind,
+            daily_cap,
+            added_at: env.ledger().timestamp(),
+        };
+        let issuers_key = Symbol::new(&env, "ISSUERS");
+        let mut issuers: Map<Address, Issuer> = env
+            .storage()
+            .instance()
+            .get(&issuers_key)
+            .unwrap_or_else(|| Map::new(&env));
+        issuers.set(issuer.clone(), entry.clone());
+        env.storage().instance().set(&issuers_key, &issuers);
+
+        retail_events::publish(
+            &env,
+            retail_events::ISSUER_ADDED,
+            &issuer,
+            IssuerUpdatedEvent {
+                daily_cap,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+        Ok(entry)
+    }
+
+    pub fn revoke_issuer(env: Env, admin: Address, issuer: Address) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        let issuers_key = Symbol::new(&env, "ISSUERS");
+        let mut issuers: Map<Address, Issuer> = env
+            .storage()
+            .instance()
+            .get(&issuers_key)
+            .unwrap_or_else(|| Map::new(&env));
+        if issuers.remove(issuer.clone()).is_none() {
+            return Err(LoyaltyError::IssuerNotFound);
+        }
+        env.storage().instance().set(&issuers_key, &issuers);
+
+        retail_events::publish(
+            &env,
+            retail_events::ISSUER_REVOKED,
+            &issuer,
+            IssuerUpdatedEvent {
+                daily_cap: 0,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn get_issuer(env: Env, issuer: Address) -> Result<Issuer, LoyaltyError> {
+        Self::get_issuers(env).get(issuer).ok_or(LoyaltyError::IssuerNotFound)
+    }
+
+    pub fn get_issuers(env: Env) -> Map<Address, Issuer> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "ISSUERS"))
+            .unwrap_or_else(|| Map::new(&env))
+    }
+
+    // Points the issuer can still hand out today
+    pub fn get_issuer_remaining_today(env: Env, issuer: Address) -> Result<i128, LoyaltyError> {
+        let entry = Self::get_issuer(env.clone(), issuer.clone())?;
+        let issued: i128 = env
+            .storage()
+            .temporary()
+            .get(&DataKey::IssuedOnDay(issuer, env.ledger().timestamp() / 86_400))
+            .unwrap_or(0);
+        Ok(entry.daily_cap.saturating_sub(issued).max(0))
+    }
+
+    // Cashier issuers are checked against this contract
+    pub fn set_employee_manager(env: Env, admin: Address, employee_manager: Address) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        env.storage().instance().set(&Symbol::new(&env, "EMPLOYEE_MANAGER"), &employee_manager);
+        Ok(())
+    }
+
+    // Gift points to another member; the fee is charged on top and burned
+    pub fn transfer_points(
+        env: Env,
+        from: Address,
+        to: Address,
+        amount: i128,
+        reason: String,
+        reference_id: String,
+    ) -> Result<PointsTransaction, LoyaltyError> {
+        from.require_auth();
+
+        let policy = Self::get_transfer_policy(env.clone());
+        if !policy.enabled {
+            return Err(LoyaltyError::TransfersDisabled);
+        }
+        Self::_require_can_transfer(&env, &from)?;
+        Self::_require_can_transfer(&env, &to)?;
+
+        if from == to || amount <= 0 || amount < policy.min_amount {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+        if policy.max_amount > 0 && amount > policy.max_amount {
+            return Err(LoyaltyError::TransferLimitExceeded);
+        }
+
+        if policy.daily_limit > 0 {
+            let usage_key = DataKey::TransferredOnDay(from.clone(), env.ledger().timestamp() / 86_400);
+            let sent: i128 = env.storage().temporary().get(&usage_key).unwrap_or(0);
+            let sent = math::add(sent, amount)?;
+            if sent > policy.daily_limit {
+                return Err(LoyaltyError::TransferLimitExceeded);
+            }
+            env.storage().temporary().set(&usage_key, &sent);
+            env.storage().temporary().extend_ttl(&usage_key, DAY_IN_LEDGERS, 2 * DAY_IN_LEDGERS);
+        }
+
+        let fee = math::mul_div(amount, policy.fee_bps as i128, 10_000)?;
+
+        // The fee comes out of the oldest points, the recipient gets the rest with their dates
+        Self::_expire_lots(&env, &from)?;
+        let taken = Self::_consume_lots(&env, &from, math::add(amount, fee)?)?;
+        let mut moved = Vec::new(&env);
+        let mut fee_left = fee;
+        for mut lot in taken.iter() {
+            let burned = lot.amount.min(fee_left);
+            fee_left = math::sub(fee_left, burned)?;
+            lot.amount = math::sub(lot.amount, burned)?;
+            if lot.amount > 0 {
+                moved.push_back(lot);
+            }
+        }
+        Self::_merge_lots(&env, &to, &moved);
+
+        let sent = Self::_record(
+            &env,
+            &from,
+            PointsTxKind::TransferOut,
+            math::sub(0, math::add(amount, fee)?)?,
+            reason.clone(),
+            reference_id.clone(),
+            Some(to.clone()),
+        )?;
+        Self::_record(&env, &to, PointsTxKind::TransferIn, amount, reason, reference_id, Some(from))?;
+        Ok(sent)
+    }
+
+    pub fn set_transfer_policy(env: Env, admin: Address, policy: TransferPolicy) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        if policy.fee_bps > 10_000 || policy.min_amount < 0 || policy.max_amount < 0 || policy.daily_limit < 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        env.storage().instance().set(&Symbol::new(&env, "TRANSFER_POLICY"), &policy);
+        Ok(())
+    }
+
+    pub fn get_transfer_policy(env: Env) -> TransferPolicy {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "TRANSFER_POLICY"))
+            .unwrap_or(TransferPolicy {
+                enabled: true,
+                fee_bps: 0,
+                min_amount: 1,
+                max_amount: 0,
+                daily_limit: 0,
+            })
+    }
+
+    // Fraud control: a blocked account can neither send, receive nor pool points
+    pub fn set_transfer_blocked(env: Env, admin: Address, account: Address, blocked: bool) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        env.storage().persistent().set(&DataKey::TransferBlocked(account.clone()), &blocked);
+        retail_events::publish(
+            &env,
+            retail_events::TRANSFER_BLOCKED,
+            &account,
+            TransferBlockedEvent {
+                blocked,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+        Ok(())
+    }
+
+    pub fn create_household(env: Env, primary: Address) -> Result<Household, LoyaltyError> {
+        primary.require_auth();
+        Self::_require_can_transfer(&env, &primary)?;
+        if env.storage().persistent().has(&DataKey::HouseholdOf(primary.clone())) {
+            return Err(LoyaltyError::AlreadyInHousehold);
+        }
+
+        let counter_key = Symbol::new(&env, "HOUSEHOLD_COUNTER");
+        let household_id: u32 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &household_id);
+
+        let household = Household {
+            household_id,
+            primary: primary.clone(),
+            members: Vec::from_array(&env, [primary.clone()]),
+            created_at: env.ledger().timestamp(),
+        };
+        env.storage().persistent().set(&DataKey::Household(household_id), &household);
+        env.storage().persistent().set(&DataKey::HouseholdOf(primary), &household_id);
+        Ok(household)
+    }
+
+    // Both the primary and the joining member have to sign
+    pub fn join_household(env: Env, primary: Address, member: Address) -> Result<Household, LoyaltyError> {
+        primary.require_auth();
+        member.require_auth();
+        Self::_require_can_transfer(&env, &member)?;
+
+        let mut household = Self::_primary_household(&env, &primary)?;
+        if env.storage().persistent().has(&DataKey::HouseholdOf(member.clone())) {
+            return Err(LoyaltyError::AlreadyInHousehold);
+        }
+        if household.members.len() >= MAX_HOUSEHOLD_MEMBERS {
+            return Err(LoyaltyError::HouseholdFull);
+        }
+
+        household.members.push_back(member.clone());
+        env.storage().persistent().set(&DataKey::Household(household.household_id), &household);
+        env.storage().persistent().set(&DataKey::HouseholdOf(member), &household.household_id);
+        Ok(household)
+    }
+
+    // The primary can remove anyone else; members can always leave on their own
+    pub fn leave_household(env: Env, caller: Address, member: Address) -> Result<Household, LoyaltyError> {
+        caller.require_auth();
+
+        let household = Self::get_household_of(env.clone(), member.clone())?;
+        if caller != member && caller != household.primary {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        if member == household.primary {
+            // Use dissolve_household to close the group
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        let mut updated = household.clone();
+        if let Some(index) = household.members.first_index_of(&member) {
+            updated.members.remove(index);
+        }
+        env.storage().persistent().set(&DataKey::Household(household.household_id), &updated);
+        env.storage().persistent().remove(&DataKey::HouseholdOf(member));
+        Ok(updated)
+    }
+
+    pub fn dissolve_household(env: Env, primary: Address) -> Result<(), LoyaltyError> {
+        primary.require_auth();
+
+        let household = Self::_primary_household(&env, &primary)?;
+        for member in household.members.iter() {
+            env.storage().persistent().remove(&DataKey::HouseholdOf(member));
+        }
+        env.storage().persistent().remove(&DataKey::Household(household.household_id));
+        Ok(())
+    }
+
+    pub fn get_household(env: Env, household_id: u32) -> Result<Household, LoyaltyError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Household(household_id))
+            .ok_or(LoyaltyError::HouseholdNotFound)
+    }
+
+    pub fn get_household_of(env: Env, account: Address) -> Result<Household, LoyaltyError> {
+        let household_id: u32 = env
+            .storage()
+            .persistent()
+            .get(&DataKey::HouseholdOf(account))
+            .ok_or(LoyaltyError::HouseholdNotFound)?;
+        Self::get_household(env, household_id)
+    }
+
+    pub fn get_household_balance(env: Env, household_id: u32) -> Result<i128, LoyaltyError> {
+        let household = Self::get_household(env.clone(), household_id)?;
+        let mut total: i128 = 0;
+        for member in household.members.iter() {
+            total = math::add(total, Self::_read_balance(&env, &member))?;
+        }
+        Ok(total)
+    }
+
+    // Primary spends the pool: its own points first, then each member in join order
+    pub fn redeem_household_points(
+        env: Env,
+        primary: Address,
+        amount: i128,
+        reason: String,
+        reference_id: String,
+    ) -> Result<i128, LoyaltyError> {
+        primary.require_auth();
+
+        if amount <= 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+        let household = Self::_primary_household(&env, &primary)?;
+
+        let mut remaining = amount;
+        for member in household.members.iter() {
+            if remaining == 0 {
+                break;
+            }
+            Self::_require_can_transfer(&env, &member)?;
+            Self::_expire_lots(&env, &member)?;
+
+            let share = Self::_read_balance(&env, &member).min(remaining);
+            if share <= 0 {
+                continue;
+            }
+            Self::_consume_lots(&env, &member, share)?;
+            let counterparty = if member == primary { None } else { Some(primary.clone()) };
+            Self::_record(
+                &env,
+                &member,
+                PointsTxKind::Redeem,
+                math::sub(0, share)?,
+                reason.clone(),
+                reference_id.clone(),
+                counterparty,
+            )?;
+            remaining = math::sub(remaining, share)?;
+        }
+
+        if remaining > 0 {
+            return Err(LoyaltyError::InsufficientPoints);
+        }
+        Ok(amount)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_reward(
+        env: Env,
+        admin: Address,
+        name: String,
+        points_cost: i128,
+        stock: u32,
+        valid_from: u64,
+        valid_until: u64,
+        min_tier: CustomerTier,
+    ) -> Result<Reward, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        if points_cost <= 0 || (valid_until != 0 && valid_until <= valid_from) {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        let counter_key = Symbol::new(&env, "REWARD_COUNTER");
+        let reward_id: u32 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &reward_id);
+
+        let reward = Reward {
+            reward_id,
+            name,
+            points_cost,
+            stock,
+            valid_from,
+            valid_until,
+            min_tier,
+            active: true,
+        };
+        Self::_save_reward(&env, &reward);
+        Ok(reward)
+    }
+
+    pub fn restock_reward(env: Env, admin: Address, reward_id: u32, stock: u32) -> Result<Reward, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        let mut reward = Self::get_reward(env.clone(), reward_id)?;
+        reward.stock = stock;
+        Self::_save_reward(&env, &reward);
+        Ok(reward)
+    }
+
+    pub fn set_reward_active(env: Env, admin: Address, reward_id: u32, active: bool) -> Result<Reward, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        let mut reward = Self::get_reward(env.clone(), reward_id)?;
+        reward.active = active;
+        Self::_save_reward(&env, &reward);
+        Ok(reward)
+    }
+
+    pub fn get_reward(env: Env, reward_id: u32) -> Result<Reward, LoyaltyError> {
+        Self::get_rewards(env).get(reward_id).ok_or(LoyaltyError::RewardNotFound)
+    }
+
+    pub fn get_rewards(env: Env) -> Map<u32, Reward> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "REWARDS"))
+            .unwrap_or_else(|| Map::new(&env))
+    }
+
+    // Rewards that can be redeemed right now, ignoring tier
+    pub fn get_available_rewards(env: Env) -> Vec<Reward> {
+        let mut result = Vec::new(&env);
+        for (_, reward) in Self::get_rewards(env.clone()).iter() {
+            if Self::_reward_available(&env, &reward) {
+                result.push_back(reward);
+            }
+        }
+        result
+    }
+
+    // Tier restrictions are checked against crm_system
+    pub fn set_crm(env: Env, admin: Address, crm: Address) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        env.storage().instance().set(&Symbol::new(&env, "CRM"), &crm);
+        Ok(())
+    }
+
+    // Spends the points and reserves one unit of stock until staff hand the reward over
+    pub fn redeem_reward(env: Env, customer: Address, reward_id: u32) -> Result<RedemptionOrder, LoyaltyError> {
+        customer.require_auth();
+
+        let mut reward = Self::get_reward(env.clone(), reward_id)?;
+        if !Self::_reward_available(&env, &reward) {
+            return Err(LoyaltyError::RewardUnavailable);
+        }
+        if reward.min_tier != CustomerTier::Bronze {
+            let crm: Address = env
+                .storage()
+                .instance()
+                .get(&Symbol::new(&env, "CRM"))
+                .ok_or(LoyaltyError::CrmNotSet)?;
+            if CrmClient::new(&env, &crm).get_customer_tier(&customer) < reward.min_tier {
+                return Err(LoyaltyError::TierRestricted);
+            }
+        }
+
+        let counter_key = Symbol::new(&env, "REDEMPTION_COUNTER");
+        let order_id: u32 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &order_id);
+        let claim_code = Self::_claim_code(&env);
+
+        Self::_expire_lots(&env, &customer)?;
+        let redeemed_lots = Self::_consume_lots(&env, &customer, reward.points_cost)?;
+        Self::_record(
+            &env,
+            &customer,
+            PointsTxKind::Redeem,
+            math::sub(0, reward.points_cost)?,
+            reward.name.clone(),
+            Self::_redemption_reference(&env, order_id),
+            None,
+        )?;
+
+        reward.stock = math::sub_u32(reward.stock, 1)?;
+        Self::_save_reward(&env, &reward);
+
+        let order = RedemptionOrder {
+            order_id,
+            reward_id,
+            customer: customer.clone(),
+            points_cost: reward.points_cost,
+            claim_code,
+            redeemed_lots,
+            status: RedemptionStatus::Pending,
+            created_at: env.ledger().timestamp(),
+            fulfilled_at: None,
+            fulfilled_by: None,
+        };
+        env.storage().persistent().set(&DataKey::Redemption(order_id), &order);
+
+        let customer_key = DataKey::CustomerRedemptions(customer);
+        let mut order_ids: Vec<u32> = env
+            .storage()
+            .persistent()
+            .get(&customer_key)
+            .unwrap_or_else(|| Vec::new(&env));
+        order_ids.push_back(order_id);
+        env.storage().persistent().set(&customer_key, &order_ids);
+
+        Ok(order)
+    }
+
+    // Store staff hand over the reward after checking the customer's claim code;
+    // the customer signs too, confirming the pickup
+    pub fn fulfill_redemption(
+        env: Env,
+        staff: Address,
+        order_id: u32,
+        claim_code: String,
+    ) -> Result<RedemptionOrder, LoyaltyError> {
+        staff.require_auth();
+        if staff != Self::_admin(&env)? {
+            let employee_manager: Address = env
+                .storage()
+                .instance()
+                .get(&Symbol::new(&env, "EMPLOYEE_MANAGER"))
+                .ok_or(LoyaltyError::EmployeeManagerNotSet)?;
+            if !EmployeeManagerClient::new(&env, &employee_manager)
+                .has_permission(&staff, &Permission::FulfillRedemptions)
+            {
+                return Err(LoyaltyError::Unauthorized);
+            }
+        }
+
+        let mut order = Self::_pending_redemption(&env, order_id)?;
+        if order.claim_code != claim_code {
+            return Err(LoyaltyError::InvalidClaimCode);
+        }
+        order.customer.require_auth();
+
+        order.status = RedemptionStatus::Fulfilled;
+        order.fulfilled_at = Some(env.ledger().timestamp());
+        order.fulfilled_by = Some(staff);
+        env.storage().persistent().set(&DataKey::Redemption(order_id), &order);
+        Ok(order)
+    }
+
+    // The customer (or the admin) calls off an order not yet handed over; the points come
+    // back with their original expiry and the reserved unit returns to stock
+    pub fn cancel_redemption(env: Env, caller: Address, order_id: u32) -> Result<RedemptionOrder, LoyaltyError> {
+        caller.require_auth();
+
+        let mut order = Self::_pending_redemption(&env, order_id)?;
+        if caller != order.customer && caller != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        Self::_merge_lots(&env, &order.customer, &order.redeemed_lots);
+        Self::_record(
+            &env,
+            &order.customer,
+            PointsTxKind::Adjust,
+            order.points_cost,
+            String::from_str(&env, "redemption_cancelled"),
+            Self::_redemption_reference(&env, order_id),
+            None,
+        )?;
+
+        let mut reward = Self::get_reward(env.clone(), order.reward_id)?;
+        reward.stock = math::add_u32(reward.stock, 1)?;
+        Self::_save_reward(&env, &reward);
+
+        order.status = RedemptionStatus::Cancelled;
+        env.storage().persistent().set(&DataKey::Redemption(order_id), &order);
+        Ok(order)
+    }
+
+    pub fn get_redemption(env: Env, order_id: u32) -> Result<RedemptionOrder, LoyaltyError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Redemption(order_id))
+            .ok_or(LoyaltyError::RedemptionNotFound)
+    }
+
+    pub fn get_customer_redemptions(env: Env, customer: Address) -> Vec<RedemptionOrder> {
+        let order_ids: Vec<u32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::CustomerRedemptions(customer))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut result = Vec::new(&env);
+        for order_id in order_ids.iter() {
+            if let Ok(order) = Self::get_redemption(env.clone(), order_id) {
+                result.push_back(order);
+            }
+        }
+        result
+    }
+
+    pub fn set_expiry_period(env: Env, admin: Address, period: u64) -> Result<(), LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        if period == 0 {
+            return Err(LoyaltyError::InvalidAmount);
+        }
+
+        // Applies to points earned from now on; existing lots keep their expiry
+        env.storage().instance().set(&Symbol::new(&env, "EXPIRY_PERIOD"), &period);
+        Ok(())
+    }
+
+    pub fn get_expiry_period(env: Env) -> u64 {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "EXPIRY_PERIOD"))
+            .unwrap_or(DEFAULT_EXPIRY_PERIOD)
+    }
+
+    // Anyone may sweep an account; returns the number of points that expired
+    pub fn expire_points(env: Env, account: Address) -> Result<i128, LoyaltyError> {
+        Self::_expire_lots(&env, &account)
+    }
+
+    // Oldest first
+    pub fn get_point_lots(env: Env, account: Address) -> Vec<PointLot> {
+        Self::_load_lots(&env, &account)
+    }
+
+    // Points that expire within `within` seconds, including expired lots not yet swept
+    pub fn get_expiring_points(env: Env, account: Address, within: u64) -> i128 {
+        let cutoff = env.ledger().timestamp().saturating_add(within);
+        let mut total: i128 = 0;
+        for lot in Self::_load_lots(&env, &account).iter() {
+            if lot.expires_at <= cutoff {
+                total = total.saturating_add(lot.amount);
+            }
+        }
+        total
+    }
+
+    pub fn get_holder_count(env: Env) -> u32 {
+        env.storage().instance().get(&Symbol::new(&env, "HOLDER_COUNT")).unwrap_or(0)
+    }
+
+    // Adds accounts the holder index has never seen, such as balances from before point lots
+    // existed; those points become one lot earned now and expire one period from today.
+    // Returns how many were newly indexed.
+    pub fn index_holders(env: Env, admin: Address, accounts: Vec<Address>) -> Result<u32, LoyaltyError> {
+        admin.require_auth();
+        if admin != Self::_admin(&env)? {
+            return Err(LoyaltyError::Unauthorized);
+        }
+
+        let mut indexed = 0;
+        for account in accounts.iter() {
+            if env.storage().persistent().has(&DataKey::HolderIndex(account.clone())) {
+                continue;
+            }
+            let lots = Self::_load_lots(&env, &account);
+            if lots.is_empty() {
+                continue;
+            }
+            Self::_save_lots(&env, &account, &lots);
+            indexed += 1;
+        }
+        Ok(indexed)
+    }
+
+    // Holders with points expiring within `within` seconds, for marketing reminders;
+    // checks `limit` holders from position `start` (see get_holder_count)
+    pub fn get_accounts_expiring_soon(env: Env, within: u64, start: u32, limit: u32) -> Vec<ExpiringPoints> {
+        let cutoff = env.ledger().timestamp().saturating_add(within);
+        let end = Self::get_holder_count(env.clone()).min(start.saturating_add(limit));
+
+        let mut result = Vec::new(&env);
+        for i in start..end {
+            let Some(account) = env.storage().persistent().get::<DataKey, Address>(&DataKey::Holder(i)) else {
+                continue;
+            };
+            let lots = Self::_load_lots(&env, &account);
+            let oldest = match lots.first() {
+                Some(lot) if lot.expires_at <= cutoff => lot,
+                _ => continue,
+            };
+
+            let mut amount: i128 = 0;
+            for lot in lots.iter() {
+                if lot.expires_at <= cutoff {
+                    amount = amount.saturating_add(lot.amount);
+                }
+            }
+            result.push_back(ExpiringPoints {
+                account,
+                amount,
+                next_expiry: oldest.expires_at,
+            });
+        }
+        result
+    }
+
+    pub fn get_history_count(env: Env, account: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::HistoryCount(account)).unwrap_or(0)
     }
-}
\ No newline at end of file
+
+    // Oldest first; `limit` entries from position `start` (see get_history_count)
+    pub fn get_history(env: Env, account: Address, start: u32, limit: u32) -> Vec<PointsTransaction> {
+        let end = Self::get_history_count(env.clone(), account.clone()).min(start.saturating_add(limit));
+
+        let mut result = Vec::new(&env);
+        for position in start..end {
+            if let Some(transaction) = env.storage().persistent().get(&DataKey::History(account.clone(), position)) {
+                result.push_back(transaction);
+            }
+        }
+        result
+    }
+
+    fn _admin(env: &Env) -> Result<Address, LoyaltyError> {
+        // Lấy admin từ persistent storage, bắt buộc phải có admin đã được set
+        env.storage()
+            .persistent()
+            .get::<Symbol, Address>(&Symbol::new(env, "admin"))
+            .ok_or(LoyaltyError::NotInitialized)
+    }
+
+    // Balances used to live under the raw Address key; they are moved on first write
+    fn _read_balance(env: &Env, account: &Address) -> i128 {
+        let key = DataKey::Balance(account.clone());
+        if let Some(balance) = env.storage().persistent().get::<DataKey, i128>(&key) {
+            return balance;
+        }
+        env.storage().persistent().get::<Address, i128>(account).unwrap_or(0)
+    }
+
+    fn _charge_issuer(env: &Env, issuer: &Address, amount: i128) -> Result<(), LoyaltyError> {
+        let entry = Self::get_issuer(env.clone(), issuer.clone())?;
+
+        if entry.kind == IssuerKind::Cashier {
+            let employee_manager: Address = env
+                .storage()
+                .instance()
+                .get(&Symbol::new(env, "EMPLOYEE_MANAGER"))
+                .ok_or(LoyaltyError::EmployeeManagerNotSet)?;
+            let employees = EmployeeManagerClient::new(env, &employee_manager);
+            if !employees.has_permission(issuer, &Permission::IssueLoyaltyPoints) {
+                return Err(LoyaltyError::Unauthorized);
+            }
+        }
+
+        // Usage is tracked per UTC day and left to expire with the temporary entry
+        let usage_key = DataKey::IssuedOnDay(issuer.clone(), env.ledger().timestamp() / 86_400);
+        let issued: i128 = env.storage().temporary().get(&usage_key).unwrap_or(0);
+        let issued = math::add(issued, amount)?;
+        if issued > entry.daily_cap {
+            return Err(LoyaltyError::DailyCapExceeded);
+        }
+        env.storage().temporary().set(&usage_key, &issued);
+        env.storage().temporary().extend_ttl(&usage_key, DAY_IN_LEDGERS, 2 * DAY_IN_LEDGERS);
+        Ok(())
+    }
+
+    // Lots are kept oldest-first. Balances from before lots existed show up as one lot earned now.
+    fn _load_lots(env: &Env, account: &Address) -> Vec<PointLot> {
+        let mut lots: Vec<PointLot> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::Lots(account.clone()))
+            .unwrap_or_else(|| Vec::new(env));
+
+        let mut tracked: i128 = 0;
+        for lot in lots.iter() {
+            tracked = tracked.saturating_add(lot.amount);
+        }
+        let untracked = Self::_read_balance(env, account).saturating_sub(tracked);
+        if untracked > 0 {
+            let now = env.ledger().timestamp();
+            lots.push_front(PointLot {
+                amount: untracked,
+                earned_at: now,
+                expires_at: now.saturating_add(Self::get_expiry_period(env.clone())),
+            });
+        }
+        lots
+    }
+
+    fn _save_lots(env: &Env, account: &Address, lots: &Vec<PointLot>) {
+        let key = DataKey::Lots(account.clone());
+        Self::_index_holder(env, account);
+
+        env.storage().persistent().set(&key, lots);
+        env.storage().persistent().extend_ttl(&key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    fn _index_holder(env: &Env, account: &Address) {
+        let index_key = DataKey::HolderIndex(account.clone());
+        if env.storage().persistent().has(&index_key) {
+            return;
+        }
+
+        let count_key = Symbol::new(env, "HOLDER_COUNT");
+        let position: u32 = env.storage().instance().get(&count_key).unwrap_or(0);
+        let holder_key = DataKey::Holder(position);
+        env.storage().persistent().set(&holder_key, account);
+        env.storage().persistent().extend_ttl(&holder_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        env.storage().persistent().set(&index_key, &position);
+        env.storage().persistent().extend_ttl(&index_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        env.storage().instance().set(&count_key, &(position + 1));
+    }
+
+    fn _add_lot(env: &Env, account: &Address, amount: i128) {
+        let now = env.ledger().timestamp();
+        let expiry_period = Self::get_expiry_period(env.clone());
+
+        let mut lots = Self::_load_lots(env, account);
+        lots.push_back(PointLot {
+            amount,
+            earned_at: now,
+            expires_at: now.saturating_add(expiry_period),
+        });
+        Self::_save_lots(env, account, &lots);
+    }
+
+    // Takes `amount` oldest-first and returns the pieces taken, still carrying their dates
+    fn _consume_lots(env: &Env, account: &Address, amount: i128) -> Result<Vec<PointLot>, LoyaltyError> {
+        let mut lots = Self::_load_lots(env, account);
+        let mut taken = Vec::new(env);
+        let mut remaining = amount;
+        while remaining > 0 {
+            let mut lot = lots.pop_front().ok_or(LoyaltyError::InsufficientPoints)?;
+            if lot.amount > remaining {
+                let mut piece = lot.clone();
+                piece.amount = remaining;
+                taken.push_back(piece);
+
+                lot.amount = math::sub(lot.amount, remaining)?;
+                lots.push_front(lot);
+                remaining = 0;
+            } else {
+                remaining = math::sub(remaining, lot.amount)?;
+                taken.push_back(lot);
+            }
+        }
+        Self::_save_lots(env, account, &lots);
+        Ok(taken)
+    }
+
+    // Transferred points keep their original expiry so gifting cannot extend their life
+    fn _merge_lots(env: &Env, account: &Address, incoming: &Vec<PointLot>) {
+        let mut lots = Self::_load_lots(env, account);
+        for lot in incoming.iter() {
+            let mut index = lots.len();
+            while index > 0 && lots.get(index - 1).unwrap().earned_at > lot.earned_at {
+                index -= 1;
+            }
+            lots.insert(index, lot);
+        }
+        Self::_save_lots(env, account, &lots);
+    }
+
+    fn _save_reward(env: &Env, reward: &Reward) {
+        let rewards_key = Symbol::new(env, "REWARDS");
+        let mut rewards = Self::get_rewards(env.clone());
+        rewards.set(reward.reward_id, reward.clone());
+        env.storage().instance().set(&rewards_key, &rewards);
+    }
+
+    fn _reward_available(env: &Env, reward: &Reward) -> bool {
+        let now = env.ledger().timestamp();
+        reward.active
+            && reward.stock > 0
+            && now >= reward.valid_from
+            && (reward.valid_until == 0 || now < reward.valid_until)
+    }
+
+    fn _pending_redemption(env: &Env, order_id: u32) -> Result<RedemptionOrder, LoyaltyError> {
+        let order = Self::get_redemption(env.clone(), order_id)?;
+        match order.status {
+            RedemptionStatus::Pending => Ok(order),
+            RedemptionStatus::Fulfilled => Err(LoyaltyError::AlreadyFulfilled),
+            RedemptionStatus::Cancelled => Err(LoyaltyError::RedemptionCancelled),
+        }
+    }
+
+    // 10 uppercase hex characters drawn from the ledger PRNG
+    fn _claim_code(env: &Env) -> String {
+        const HEX: &[u8; 16] = b"0123456789ABCDEF";
+        let mut bits: u64 = env.prng().gen();
+
+        let mut code = [0u8; 10];
+        for digit in code.iter_mut() {
+            *digit = HEX[(bits & 0x0f) as usize];
+            bits >>= 4;
+        }
+        String::from_bytes(env, &code)
+    }
+
+    // History reference of a redemption: "R" and the zero-padded order id
+    fn _redemption_reference(env: &Env, order_id: u32) -> String {
+        let mut reference = *b"R0000000000";
+        let mut rest = order_id;
+        for digit in reference.iter_mut().skip(1).rev() {
+            *digit = b'0' + (rest % 10) as u8;
+            rest /= 10;
+        }
+        String::from_bytes(env, &reference)
+    }
+
+    fn _primary_household(env: &Env, primary: &Address) -> Result<Household, LoyaltyError> {
+        let household = Self::get_household_of(env.clone(), primary.clone())?;
+        if household.primary != *primary {
+            return Err(LoyaltyError::Unauthorized);
+        }
+        Ok(household)
+    }
+
+    fn _require_can_transfer(env: &Env, account: &Address) -> Result<(), LoyaltyError> {
+        let blocked: bool = env
+            .storage()
+            .persistent()
+            .get(&DataKey::TransferBlocked(account.clone()))
+            .unwrap_or(false);
+        if blocked {
+            return Err(LoyaltyError::TransfersDisabled);
+        }
+        Ok(())
+    }
+
+    fn _expire_lots(env: &Env, account: &Address) -> Result<i128, LoyaltyError> {
+        let now = env.ledger().timestamp();
+        let lots = Self::_load_lots(env, account);
+
+        let mut kept = Vec::new(env);
+        let mut expired: i128 = 0;
+        for lot in lots.iter() {
+            if lot.expires_at <= now {
+                expired = math::add(expired, lot.amount)?;
+            } else {
+                kept.push_back(lot);
+            }
+        }
+        if expired == 0 {
+            return Ok(0);
+        }
+
+        Self::_save_lots(env, account, &kept);
+        Self::_record(
+            env,
+            account,
+            PointsTxKind::Expire,
+            math::sub(0, expired)?,
+            String::from_str(env, "expired"),
+            String::from_str(env, ""),
+            None,
+        )?;
+        Ok(expired)
+    }
+
+    // Applies `amount` to the balance, appends to the account history and emits the matching event
+    fn _record(
+        env: &Env,
+        account: &Address,
+        kind: PointsTxKind,
+        amount: i128,
+        reason: String,
+        reference_id: String,
+        counterparty: Option<Address>,
+    ) -> Result<PointsTransaction, LoyaltyError> {
+        let balance_after = math::add(Self::_read_balance(env, account), amount)?;
+        if balance_after < 0 {
+            return Err(LoyaltyError::InsufficientPoints);
+        }
+
+        let balance_key = DataKey::Balance(account.clone());
+        env.storage().persistent().set(&balance_key, &balance_after);
+        env.storage().persistent().extend_ttl(&balance_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        if env.storage().persistent().has(account) {
+            env.storage().persistent().remove(account);
+        }
+
+        let counter_key = Symbol::new(env, "TX_COUNTER");
+        let tx_id: u64 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &tx_id);
+
+        let transaction = PointsTransaction {
+            tx_id,
+            kind,
+            amount,
+            balance_after,
+            reason,
+            reference_id,
+            counterparty,
+            timestamp: env.ledger().timestamp(),
+        };
+
+        // One entry per movement, so the cost of recording never grows with the history
+        let count_key = DataKey::HistoryCount(account.clone());
+        let position: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        let history_key = DataKey::History(account.clone(), position);
+        env.storage().persistent().set(&history_key, &transaction);
+        env.storage().persistent().extend_ttl(&history_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+        env.storage().persistent().set(&count_key, &math::add_u32(position, 1)?);
+        env.storage().persistent().extend_ttl(&count_key, LIFETIME_THRESHOLD, BUMP_AMOUNT);
+
+        let event_name = match kind {
+            PointsTxKind::Earn => retail_events::POINTS_EARNED,
+            PointsTxKind::Redeem => retail_events::POINTS_REDEEMED,
+            PointsTxKind::Expire => retail_events::POINTS_EXPIRED,
+            PointsTxKind::Adjust => retail_events::POINTS_ADJUSTED,
+            PointsTxKind::TransferIn | PointsTxKind::TransferOut => retail_events::POINTS_TRANSFERRED,
+        };
+        retail_events::publish(
+            env,
+            event_name,
+            account,
+            PointsMovedEvent {
+                tx_id,
+                amount,
+                balance_after,
+                reason: transaction.reason.clone(),
+                reference_id: transaction.reference_id.clone(),
+                timestamp: transaction.timestamp,
+            },
+        );
+
+        Ok(transaction)
+    }
+}
+
+mod test;
\ No newline at end of file
@@ -0,0 +1,303 @@
+#![cfg(test)]
+
+use super::*;
+use retail_events::{EventTopics, IssuerUpdatedEvent, TransferBlockedEvent};
+use soroban_sdk::{
+    contract, contractimpl,
+    testutils::{Address as _, Events, Ledger, MockAuth, MockAuthInvoke},
+    Env, IntoVal, String,
+};
+
+// Stand-in for employee_manager: wallets only hold the permissions granted here
+#[contract]
+struct MockEmployeeManager;
+
+#[contractimpl]
+impl MockEmployeeManager {
+    pub fn grant(env: Env, employee_address: Address, permission: Permission) {
+        env.storage().instance().set(&(employee_address, permission), &true);
+    }
+
+    pub fn has_permission(env: Env, employee_address: Address, required_permission: Permission) -> bool {
+        env.storage().instance().get(&(employee_address, required_permission)).unwrap_or(false)
+    }
+}
+
+// Stand-in for crm_system: every customer is Silver
+#[contract]
+struct MockCrm;
+
+#[contractimpl]
+impl MockCrm {
+    pub fn get_customer_tier(_env: Env, _wallet_address: Address) -> CustomerTier {
+        CustomerTier::Silver
+    }
+}
+
+#[test]
+fn test_points_ledger() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+
+    let reason = String::from_str(&env, "purchase");
+    let sale = String::from_str(&env, "SALE_1");
+    assert!(client.try_issue_points(&admin, &customer, &-5, &reason, &sale).is_err());
+    client.issue_points(&admin, &customer, &500, &reason, &sale);
+    client.redeem_points(&customer, &200, &String::from_str(&env, "voucher"), &String::from_str(&env, "RDM_1"));
+    assert!(client.try_redeem_points(&customer, &301, &reason, &sale).is_err());
+    client.adjust_points(&admin, &customer, &-50, &String::from_str(&env, "goodwill reversal"), &sale);
+
+    let history = client.get_history(&customer, &0, &10);
+    assert_eq!(history.len(), 3);
+    assert_eq!(client.get_history_count(&customer), 3);
+    let page = client.get_history(&customer, &1, &1);
+    assert_eq!(page.len(), 1);
+    assert_eq!(page.get(0).unwrap().amount, -200);
+    assert_eq!(history.get(1).unwrap().kind, PointsTxKind::Redeem);
+    assert_eq!(history.get(1).unwrap().amount, -200);
+    assert_eq!(history.get(2).unwrap().balance_after, 250);
+    assert_eq!(client.get_balance(&customer), 250);
+}
+
+#[test]
+fn test_points_expire_oldest_first() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.set_expiry_period(&admin, &1_000);
+
+    let reason = String::from_str(&env, "purchase");
+    let sale = String::from_str(&env, "SALE_1");
+    client.issue_points(&admin, &customer, &100, &reason, &sale);
+    env.ledger().with_mut(|li| li.timestamp = 400);
+    client.issue_points(&admin, &customer, &50, &reason, &sale);
+
+    // Redemption eats into the oldest lot first
+    client.redeem_points(&customer, &30, &reason, &sale);
+    assert_eq!(client.get_point_lots(&customer).get(0).unwrap().amount, 70);
+    assert_eq!(client.get_expiring_points(&customer, &700), 70);
+    assert_eq!(client.get_accounts_expiring_soon(&700, &0, &10).len(), 1);
+    assert_eq!(client.get_accounts_expiring_soon(&500, &0, &10).len(), 0);
+
+    // A balance from before lots existed is only listed once indexed
+    let legacy = Address::generate(&env);
+    env.as_contract(&contract_id, || env.storage().persistent().set(&legacy, &40_i128));
+    assert_eq!(client.get_holder_count(), 1);
+    assert!(client.try_index_holders(&legacy, &Vec::from_array(&env, [legacy.clone()])).is_err());
+    assert_eq!(client.index_holders(&admin, &Vec::from_array(&env, [legacy.clone(), customer.clone()])), 1);
+    assert_eq!(client.get_holder_count(), 2);
+    assert_eq!(client.get_accounts_expiring_soon(&1_000, &0, &10).len(), 2);
+    assert_eq!(client.get_accounts_expiring_soon(&1_000, &1, &10).get(0).unwrap().account, legacy);
+
+    env.ledger().with_mut(|li| li.timestamp = 1_000);
+    assert_eq!(client.expire_points(&customer), 70);
+    assert_eq!(client.expire_points(&customer), 0);
+    assert_eq!(client.get_balance(&customer), 50);
+    assert_eq!(client.get_history(&customer, &0, &10).last().unwrap().kind, PointsTxKind::Expire);
+    assert!(client.try_redeem_points(&customer, &51, &reason, &sale).is_err());
+}
+
+#[test]
+fn test_delegated_issuers() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let pos = Address::generate(&env);
+    let cashier = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+
+    let employees_id = env.register_contract(None, MockEmployeeManager);
+    let employees = MockEmployeeManagerClient::new(&env, &employees_id);
+    client.set_employee_manager(&admin, &employees_id);
+
+    let reason = String::from_str(&env, "purchase");
+    let sale = String::from_str(&env, "SALE_1");
+    assert!(client.try_issue_points(&pos, &customer, &10, &reason, &sale).is_err());
+
+    client.add_issuer(&admin, &pos, &IssuerKind::Contract, &1_000);
+    let (emitter, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::ISSUER_ADDED), retail_events::EVENT_VERSION, pos.clone());
+    assert_eq!(emitter, contract_id);
+    assert_eq!(topics, expected.into_val(&env));
+    let added: IssuerUpdatedEvent = data.into_val(&env);
+    assert_eq!(added.daily_cap, 1_000);
+    client.issue_points(&pos, &customer, &600, &reason, &sale);
+    assert!(client.try_issue_points(&pos, &customer, &401, &reason, &sale).is_err());
+    assert_eq!(client.get_issuer_remaining_today(&pos), 400);
+
+    // The cap resets on the next day
+    env.ledger().with_mut(|li| li.timestamp = 86_400);
+    client.issue_points(&pos, &customer, &1_000, &reason, &sale);
+
+    // Cashiers also need the permission in employee_manager
+    client.add_issuer(&admin, &cashier, &IssuerKind::Cashier, &100);
+    assert!(client.try_issue_points(&cashier, &customer, &50, &reason, &sale).is_err());
+    employees.grant(&cashier, &Permission::IssueLoyaltyPoints);
+    client.issue_points(&cashier, &customer, &50, &reason, &sale);
+
+    client.revoke_issuer(&admin, &cashier);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::ISSUER_REVOKED), retail_events::EVENT_VERSION, cashier.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let revoked: IssuerUpdatedEvent = data.into_val(&env);
+    assert_eq!(revoked.daily_cap, 0);
+    assert!(client.try_issue_points(&cashier, &customer, &50, &reason, &sale).is_err());
+    assert_eq!(client.get_balance(&customer), 1_650);
+}
+
+#[test]
+fn test_transfers_and_households() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let parent = Address::generate(&env);
+    let child = Address::generate(&env);
+    let friend = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+
+    let reason = String::from_str(&env, "gift");
+    let reference = String::from_str(&env, "GIFT_1");
+    client.issue_points(&admin, &child, &1_000, &reason, &reference);
+    client.set_transfer_policy(
+        &admin,
+        &TransferPolicy { enabled: true, fee_bps: 1_000, min_amount: 10, max_amount: 500, daily_limit: 600 },
+    );
+
+    // 10% fee on top, burned; received points keep the sender's expiry
+    client.transfer_points(&child, &friend, &300, &reason, &reference);
+    assert_eq!(client.get_balance(&child), 670);
+    assert_eq!(client.get_balance(&friend), 300);
+    assert_eq!(
+        client.get_point_lots(&friend).get(0).unwrap().expires_at,
+        client.get_point_lots(&child).get(0).unwrap().expires_at
+    );
+    assert!(client.try_transfer_points(&child, &friend, &501, &reason, &reference).is_err());
+    assert!(client.try_transfer_points(&child, &friend, &301, &reason, &reference).is_err());
+
+    client.set_transfer_blocked(&admin, &friend, &true);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::TRANSFER_BLOCKED), retail_events::EVENT_VERSION, friend.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let blocked: TransferBlockedEvent = data.into_val(&env);
+    assert!(blocked.blocked);
+    assert!(client.try_transfer_points(&friend, &child, &50, &reason, &reference).is_err());
+
+    // The primary spends its own points first, then the members'
+    client.issue_points(&admin, &parent, &100, &reason, &reference);
+    let household = client.create_household(&parent);
+    client.join_household(&parent, &child);
+    assert!(client.try_join_household(&parent, &friend).is_err());
+    assert_eq!(client.get_household_balance(&household.household_id), 770);
+    assert!(client.try_redeem_household_points(&child, &10, &reason, &reference).is_err());
+
+    client.redeem_household_points(&parent, &250, &reason, &reference);
+    assert_eq!(client.get_balance(&parent), 0);
+    assert_eq!(client.get_balance(&child), 520);
+    assert_eq!(client.get_history(&child, &0, &10).last().unwrap().counterparty, Some(parent.clone()));
+    assert!(client.try_redeem_household_points(&parent, &521, &reason, &reference).is_err());
+}
+
+#[test]
+fn test_rewards_catalog() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let cashier = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.set_crm(&admin, &env.register_contract(None, MockCrm));
+    let employees_id = env.register_contract(None, MockEmployeeManager);
+    client.set_employee_manager(&admin, &employees_id);
+
+    let reason = String::from_str(&env, "purchase");
+    client.issue_points(&admin, &customer, &1_000, &reason, &String::from_str(&env, "SALE_1"));
+
+    let coffee = client.add_reward(&admin, &String::from_str(&env, "Coffee"), &300, &1, &0, &0, &CustomerTier::Bronze);
+    let lounge = client.add_reward(&admin, &String::from_str(&env, "Lounge"), &100, &5, &0, &0, &CustomerTier::Gold);
+    assert!(client.try_redeem_reward(&customer, &lounge.reward_id).is_err());
+
+    let order = client.redeem_reward(&customer, &coffee.reward_id);
+    assert_eq!(order.claim_code.len(), 10);
+    assert_eq!(client.get_balance(&customer), 700);
+    assert_eq!(client.get_reward(&coffee.reward_id).stock, 0);
+    assert!(client.try_redeem_reward(&customer, &coffee.reward_id).is_err());
+    assert_eq!(client.get_available_rewards().len(), 1);
+
+    // Cancelling gives back the points with their expiry and the unit of stock
+    assert!(client.try_cancel_redemption(&cashier, &order.order_id).is_err());
+    let cancelled = client.cancel_redemption(&customer, &order.order_id);
+    assert_eq!(cancelled.status, RedemptionStatus::Cancelled);
+    assert_eq!(client.get_balance(&customer), 1_000);
+    assert_eq!(client.get_point_lots(&customer).len(), 2);
+    assert_eq!(client.get_reward(&coffee.reward_id).stock, 1);
+    assert!(client.try_cancel_redemption(&customer, &order.order_id).is_err());
+
+    // Only staff allowed to fulfil redemptions can hand it over, with the right claim code, once
+    let order = client.redeem_reward(&customer, &coffee.reward_id);
+    let employees = MockEmployeeManagerClient::new(&env, &employees_id);
+    employees.grant(&cashier, &Permission::IssueLoyaltyPoints);
+    assert!(client.try_fulfill_redemption(&cashier, &order.order_id, &order.claim_code).is_err());
+    employees.grant(&cashier, &Permission::FulfillRedemptions);
+    assert!(client
+        .try_fulfill_redemption(&cashier, &order.order_id, &String::from_str(&env, "0000000000"))
+        .is_err());
+    let fulfilled = client.fulfill_redemption(&cashier, &order.order_id, &order.claim_code);
+    assert_eq!(fulfilled.status, RedemptionStatus::Fulfilled);
+    assert_eq!(fulfilled.fulfilled_by, Some(cashier.clone()));
+    assert!(client.try_fulfill_redemption(&cashier, &order.order_id, &order.claim_code).is_err());
+    assert!(client.try_cancel_redemption(&customer, &order.order_id).is_err());
+    assert_eq!(client.get_customer_redemptions(&customer).len(), 2);
+}
+
+#[test]
+fn test_fulfillment_needs_customer_signature() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let contract_id = env.register_contract(None, LoyaltyToken);
+    let client = LoyaltyTokenClient::new(&env, &contract_id);
+    client.init(&admin);
+    client.issue_points(&admin, &customer, &500, &String::from_str(&env, "purchase"), &String::from_str(&env, "SALE_1"));
+    let coffee = client.add_reward(&admin, &String::from_str(&env, "Coffee"), &300, &1, &0, &0, &CustomerTier::Bronze);
+    let order = client.redeem_reward(&customer, &coffee.reward_id);
+
+    // Only the admin signs: the customer never confirmed the pickup
+    env.mock_auths(&[MockAuth {
+        address: &admin,
+        invoke: &MockAuthInvoke {
+            contract: &contract_id,
+            fn_name: "fulfill_redemption",
+            args: (admin.clone(), order.order_id, order.claim_code.clone()).into_val(&env),
+            sub_invokes: &[],
+        },
+    }]);
+    assert!(client.try_fulfill_redemption(&admin, &order.order_id, &order.claim_code).is_err());
+
+    env.mock_all_auths();
+    client.fulfill_redemption(&admin, &order.order_id, &order.claim_code);
+    let auths = env.auths();
+    assert!(auths.iter().any(|(address, _)| *address == customer));
+}
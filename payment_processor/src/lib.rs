@@ -1,11 +1,17 @@
 ﻿#![no_std]
 use soroban_sdk::{
-    contract, contractimpl, contracttype, Address, Env, String, Symbol, Vec, token,
+    contract, contractimpl, contracttype, token, Address, Env, IntoVal, Map, String, Symbol, TryFromVal, Val, Vec,
 };
+use retail_events::{PaymentCreatedEvent, PaymentRefundedEvent, PaymentSettledEvent};
+use shared::{math, DashboardClient, MetricEvent, RetailError};
 
 #[contract]
 pub struct PaymentProcessor;
 
+const BUMP_THRESHOLD: u32 = 17_280; // ~1 day
+const BUMP_AMOUNT: u32 = 518_400; // ~30 days
+const MAX_SUBSCRIPTION_INTERVAL: u64 = 366 * 86_400;
+
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
 pub struct PaymentRecord {
@@ -13,15 +19,157 @@ pub struct PaymentRecord {
     pub customer: Address,
     pub merchant: Address,
     pub amount: i128,
+    pub token: Address,
+    pub fee_amount: i128,
     pub timestamp: u64,
 }
 
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeSchedule {
+    pub percentage_bps: u32,
+    pub fixed_fee: i128,
+    pub min_fee: i128,
+    pub max_fee: i128, // 0 = uncapped
+}
+
+// Most specific scope wins: MerchantToken, Merchant, Token, then Default
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum FeeScope {
+    Default,
+    Token(Address),
+    Merchant(Address),
+    MerchantToken(Address, Address),
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct FeeRevenueSummary {
+    pub token: Address,
+    pub payment_count: u32,
+    pub gross_volume: i128,
+    pub transaction_fees: i128,
+    pub settlement_fees: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SubscriptionStatus {
+    Active,
+    PastDue,
+    Cancelled,
+    Lapsed,
+    Completed,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct Subscription {
+    pub subscription_id: u32,
+    pub customer: Address,
+    pub merchant: Address,
+    pub token: Address,
+    pub amount: i128,
+    pub interval: u64,
+    pub max_total: i128, // 0 = no cap
+    pub total_charged: i128,
+    pub next_charge_at: u64,
+    pub last_attempt_at: u64,
+    pub failed_attempts: u32,
+    pub status: SubscriptionStatus,
+    pub created_at: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubscriptionPolicy {
+    pub grace_period: u64,
+    pub retry_interval: u64,
+    pub max_retries: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PayoutSchedule {
+    Daily,
+    Weekly,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettlementConfig {
+    pub merchant: Address,
+    pub token: Address,
+    pub schedule: PayoutSchedule,
+    pub fee_bps: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PendingSettlement {
+    pub merchant: Address,
+    pub token: Address,
+    pub period_start: u64,
+    pub payment_count: u32,
+    pub gross_amount: i128,
+    pub fee_amount: i128,
+    pub refund_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettlementReport {
+    pub batch_id: u32,
+    pub merchant: Address,
+    pub token: Address,
+    pub period_start: u64,
+    pub period_end: u64,
+    pub payment_count: u32,
+    pub gross_amount: i128,
+    pub fee_amount: i128,
+    pub refund_amount: i128,
+    pub net_amount: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    Payment(String),
+    Refunded(String), // total refunded against a payment
+    PaymentAt(u32), // payment id by position across all payments
+    CustomerPayment(Address, u32), // customer, position in the customer's payments
+    CustomerPaymentCount(Address),
+    MerchantPayment(Address, u32),
+    MerchantPaymentCount(Address),
+    Settlement(u32), // settlement report by batch id
+    MerchantSettlement(Address, u32), // batch id by position in the merchant's settlements
+    MerchantSettlementCount(Address),
+    Subscription(u32),
+    CustomerSubscription(Address, u32), // subscription id by position
+    CustomerSubscriptionCount(Address),
+    MerchantSubscription(Address, u32),
+    MerchantSubscriptionCount(Address),
+}
+
 #[contracttype]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PaymentError {
     Unauthorized = 0,
     InvalidAmount = 1,
     PaymentNotFound = 2,
+    AlreadyInitialized = 3,
+    NotInitialized = 4,
+    SettlementNotConfigured = 5,
+    SettlementNotDue = 6,
+    InsufficientHeldFunds = 7,
+    FeeAccountNotSet = 8,
+    SettlementPending = 9,
+    SubscriptionNotFound = 10,
+    SubscriptionInactive = 11,
+    ChargeNotDue = 12,
+    Overflow = 13,
+    NothingToSettle = 14,
 }
 
 impl From<PaymentError> for soroban_sdk::Error {
@@ -30,6 +178,18 @@ impl From<PaymentError> for soroban_sdk::Error {
             PaymentError::Unauthorized => soroban_sdk::Error::from_contract_error(0),
             PaymentError::InvalidAmount => soroban_sdk::Error::from_contract_error(1),
             PaymentError::PaymentNotFound => soroban_sdk::Error::from_contract_error(2),
+            PaymentError::AlreadyInitialized => soroban_sdk::Error::from_contract_error(3),
+            PaymentError::NotInitialized => soroban_sdk::Error::from_contract_error(4),
+            PaymentError::SettlementNotConfigured => soroban_sdk::Error::from_contract_error(5),
+            PaymentError::SettlementNotDue => soroban_sdk::Error::from_contract_error(6),
+            PaymentError::InsufficientHeldFunds => soroban_sdk::Error::from_contract_error(7),
+            PaymentError::FeeAccountNotSet => soroban_sdk::Error::from_contract_error(8),
+            PaymentError::SettlementPending => soroban_sdk::Error::from_contract_error(9),
+            PaymentError::SubscriptionNotFound => soroban_sdk::Error::from_contract_error(10),
+            PaymentError::SubscriptionInactive => soroban_sdk::Error::from_contract_error(11),
+            PaymentError::ChargeNotDue => soroban_sdk::Error::from_contract_error(12),
+            PaymentError::Overflow => soroban_sdk::Error::from_contract_error(13),
+            PaymentError::NothingToSettle => soroban_sdk::Error::from_contract_error(14),
         }
     }
 }
@@ -40,6 +200,28 @@ impl From<&PaymentError> for soroban_sdk::Error {
             PaymentError::Unauthorized => soroban_sdk::Error::from_contract_error(0),
             PaymentError::InvalidAmount => soroban_sdk::Error::from_contract_error(1),
             PaymentError::PaymentNotFound => soroban_sdk::Error::from_contract_error(2),
+            PaymentError::AlreadyInitialized => soroban_sdk::Error::from_contract_error(3),
+            PaymentError::NotInitialized => soroban_sdk::Error::from_contract_error(4),
+            PaymentError::SettlementNotConfigured => soroban_sdk::Error::from_contract_error(5),
+            PaymentError::SettlementNotDue => soroban_sdk::Error::from_contract_error(6),
+            PaymentError::InsufficientHeldFunds => soroban_sdk::Error::from_contract_error(7),
+            PaymentError::FeeAccountNotSet => soroban_sdk::Error::from_contract_error(8),
+            PaymentError::SettlementPending => soroban_sdk::Error::from_contract_error(9),
+            PaymentError::SubscriptionNotFound => soroban_sdk::Error::from_contract_error(10),
+            PaymentError::SubscriptionInactive => soroban_sdk::Error::from_contract_error(11),
+            PaymentError::ChargeNotDue => soroban_sdk::Error::from_contract_error(12),
+            PaymentError::Overflow => soroban_sdk::Error::from_contract_error(13),
+            PaymentError::NothingToSettle => soroban_sdk::Error::from_contract_error(14),
+        }
+    }
+}
+
+// Lets shared::math results propagate with `?`
+impl From<RetailError> for PaymentError {
+    fn from(e: RetailError) -> PaymentError {
+        match e {
+            RetailError::Overflow => PaymentError::Overflow,
+            _ => PaymentError::InvalidAmount,
         }
     }
 }
@@ -53,8 +235,77 @@ impl TryFrom<soroban_sdk::Error> for PaymentError {
 
 #[contractimpl]
 impl PaymentProcessor {
-    pub fn initialize(env: Env, admin: Address) {
-        env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
+    pub fn initialize(env: Env, admin: Address) -> Result<(), PaymentError> {
+        let admin_key = Symbol::new(&env, "ADMIN");
+        if env.storage().instance().has(&admin_key) {
+            return Err(PaymentError::AlreadyInitialized);
+        }
+
+        admin.require_auth();
+        env.storage().instance().set(&admin_key, &admin);
+        env.storage().instance().set(&Symbol::new(&env, "AUDITORS"), &Vec::<Address>::new(&env));
+        Ok(())
+    }
+
+    pub fn get_admin(env: Env) -> Result<Address, PaymentError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "ADMIN"))
+            .ok_or(PaymentError::NotInitialized)
+    }
+
+    pub fn transfer_admin(env: Env, admin: Address, new_admin: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &new_admin);
+        Ok(())
+    }
+
+    pub fn add_auditor(env: Env, admin: Address, auditor: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let auditors_key = Symbol::new(&env, "AUDITORS");
+        let mut auditors: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&auditors_key)
+            .unwrap_or(Vec::new(&env));
+
+        if !auditors.contains(&auditor) {
+            auditors.push_back(auditor);
+            env.storage().instance().set(&auditors_key, &auditors);
+        }
+        Ok(())
+    }
+
+    pub fn remove_auditor(env: Env, admin: Address, auditor: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let auditors_key = Symbol::new(&env, "AUDITORS");
+        let mut auditors: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&auditors_key)
+            .unwrap_or(Vec::new(&env));
+
+        if let Some(index) = auditors.first_index_of(&auditor) {
+            auditors.remove(index);
+            env.storage().instance().set(&auditors_key, &auditors);
+        }
+        Ok(())
+    }
+
+    pub fn is_auditor(env: Env, address: Address) -> bool {
+        let auditors: Vec<Address> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "AUDITORS"))
+            .unwrap_or(Vec::new(&env));
+
+        auditors.contains(&address)
     }
 
     pub fn process_payment(
@@ -71,73 +322,950 @@ impl PaymentProcessor {
             return Err(PaymentError::InvalidAmount);
         }
 
-        // Transfer tokens
-        let token_client = token::Client::new(&env, &token);
-        token_client.transfer(&customer, &merchant, &amount);
+        Self::_collect_payment(&env, &customer, &customer, &merchant, &token, amount, "PAYMENT_")
+    }
+
+    pub fn get_customer_payment_count(env: Env, customer: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::CustomerPaymentCount(customer)).unwrap_or(0)
+    }
 
-        // Create payment record - ĐƠN GIẢN HÓA payment_id
-        let timestamp = env.ledger().timestamp();
-    
-        let payment_id = String::from_str(&env, "PAYMENT");
+    // `limit` of the customer's payments from position `start` (see get_customer_payment_count)
+    pub fn get_payment_history(env: Env, customer: Address, start: u32, limit: u32) -> Vec<PaymentRecord> {
+        let count = Self::get_customer_payment_count(env.clone(), customer.clone());
+        let ids = Self::_read_index(&env, count, start, limit, |position| {
+            DataKey::CustomerPayment(customer.clone(), position)
+        });
+        Self::_load_payments(&env, ids)
+    }
+
+    pub fn get_payment_count(env: Env) -> u32 {
+        env.storage().instance().get(&Symbol::new(&env, "PAYMENT_COUNTER")).unwrap_or(0)
+    }
+
+    // `limit` of all payments from position `start` (see get_payment_count)
+    pub fn get_all_payments(env: Env, caller: Address, start: u32, limit: u32) -> Result<Vec<PaymentRecord>, PaymentError> {
+        caller.require_auth();
+
+        // Admin and auditors may read every record; auditors have no write access
+        if Self::_require_admin(&env, &caller).is_err() && !Self::is_auditor(env.clone(), caller) {
+            return Err(PaymentError::Unauthorized);
+        }
+
+        let count = Self::get_payment_count(env.clone());
+        let ids = Self::_read_index(&env, count, start, limit, DataKey::PaymentAt);
+        Ok(Self::_load_payments(&env, ids))
+    }
+
+    pub fn get_merchant_payment_count(env: Env, merchant: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::MerchantPaymentCount(merchant)).unwrap_or(0)
+    }
+
+    // `limit` of the merchant's payments from position `start` (see get_merchant_payment_count)
+    pub fn get_merchant_payments(env: Env, merchant: Address, start: u32, limit: u32) -> Vec<PaymentRecord> {
+        merchant.require_auth();
+
+        let count = Self::get_merchant_payment_count(env.clone(), merchant.clone());
+        let ids = Self::_read_index(&env, count, start, limit, |position| {
+            DataKey::MerchantPayment(merchant.clone(), position)
+        });
+        Self::_load_payments(&env, ids)
+    }
+
+    pub fn set_fee_account(env: Env, admin: Address, fee_account: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "FEE_ACCOUNT"), &fee_account);
+        Ok(())
+    }
+
+    // Refunds are reported to this dashboard as they happen
+    pub fn set_dashboard(env: Env, admin: Address, dashboard: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "DASHBOARD"), &dashboard);
+        Ok(())
+    }
+
+    pub fn get_dashboard(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Symbol::new(&env, "DASHBOARD"))
+    }
+
+    pub fn configure_settlement(
+        env: Env,
+        admin: Address,
+        merchant: Address,
+        token: Address,
+        schedule: PayoutSchedule,
+        fee_bps: u32,
+    ) -> Result<SettlementConfig, PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        if fee_bps > 10_000 {
+            return Err(PaymentError::InvalidAmount);
+        }
+        if !env.storage().instance().has(&Symbol::new(&env, "FEE_ACCOUNT")) {
+            return Err(PaymentError::FeeAccountNotSet);
+        }
+
+        // Held funds are always paid out in the token they were collected in
+        let pending_key = Symbol::new(&env, "PENDING_SETTLEMENTS");
+        let mut pending_settlements: Map<Address, PendingSettlement> = env
+            .storage()
+            .instance()
+            .get(&pending_key)
+            .unwrap_or(Map::new(&env));
+        if let Some(pending) = pending_settlements.get(merchant.clone()) {
+            if pending.token != token {
+                if Self::_held_amount(&pending)? > 0 {
+                    return Err(PaymentError::SettlementPending);
+                }
+                pending_settlements.remove(merchant.clone());
+                env.storage().instance().set(&pending_key, &pending_settlements);
+            }
+        }
+
+        let config = SettlementConfig {
+            merchant: merchant.clone(),
+            token,
+            schedule,
+            fee_bps,
+        };
+
+        let configs_key = Symbol::new(&env, "SETTLEMENT_CONFIGS");
+        let mut settlement_configs: Map<Address, SettlementConfig> = env
+            .storage()
+            .instance()
+            .get(&configs_key)
+            .unwrap_or(Map::new(&env));
+
+        settlement_configs.set(merchant, config.clone());
+        env.storage().instance().set(&configs_key, &settlement_configs);
+
+        Ok(config)
+    }
+
+    pub fn disable_settlement(env: Env, admin: Address, merchant: Address) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let configs_key = Symbol::new(&env, "SETTLEMENT_CONFIGS");
+        let mut settlement_configs: Map<Address, SettlementConfig> = env
+            .storage()
+            .instance()
+            .get(&configs_key)
+            .unwrap_or(Map::new(&env));
+
+        if !settlement_configs.contains_key(merchant.clone()) {
+            return Err(PaymentError::SettlementNotConfigured);
+        }
+
+        // Funds already held stay claimable through settle_merchant
+        settlement_configs.remove(merchant);
+        env.storage().instance().set(&configs_key, &settlement_configs);
+        Ok(())
+    }
+
+    pub fn get_settlement_config(env: Env, merchant: Address) -> Result<SettlementConfig, PaymentError> {
+        let settlement_configs: Map<Address, SettlementConfig> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "SETTLEMENT_CONFIGS"))
+            .unwrap_or(Map::new(&env));
+
+        settlement_configs.get(merchant).ok_or(PaymentError::SettlementNotConfigured)
+    }
+
+    pub fn get_pending_settlement(env: Env, merchant: Address) -> Result<PendingSettlement, PaymentError> {
+        let pending_settlements: Map<Address, PendingSettlement> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "PENDING_SETTLEMENTS"))
+            .unwrap_or(Map::new(&env));
+
+        pending_settlements.get(merchant).ok_or(PaymentError::SettlementNotConfigured)
+    }
+
+    pub fn get_payment(env: Env, payment_id: String) -> Result<PaymentRecord, PaymentError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Payment(payment_id))
+            .ok_or(PaymentError::PaymentNotFound)
+    }
+
+    pub fn get_refunded_amount(env: Env, payment_id: String) -> i128 {
+        env.storage().persistent().get(&DataKey::Refunded(payment_id)).unwrap_or(0)
+    }
+
+    // Returns held funds to the payment's customer, up to the amount of that payment
+    pub fn refund_held_payment(
+        env: Env,
+        merchant: Address,
+        payment_id: String,
+        amount: i128,
+    ) -> Result<PendingSettlement, PaymentError> {
+        merchant.require_auth();
+
+        if amount <= 0 {
+            return Err(PaymentError::InvalidAmount);
+        }
+
+        let payment = Self::get_payment(env.clone(), payment_id.clone())?;
+        if payment.merchant != merchant {
+            return Err(PaymentError::Unauthorized);
+        }
+        let refunded = math::add(Self::get_refunded_amount(env.clone(), payment_id.clone()), amount)?;
+        if refunded > payment.amount {
+            return Err(PaymentError::InvalidAmount);
+        }
+
+        let pending_key = Symbol::new(&env, "PENDING_SETTLEMENTS");
+        let mut pending_settlements: Map<Address, PendingSettlement> = env
+            .storage()
+            .instance()
+            .get(&pending_key)
+            .unwrap_or(Map::new(&env));
+
+        let mut pending = pending_settlements
+            .get(merchant.clone())
+            .ok_or(PaymentError::SettlementNotConfigured)?;
+
+        if pending.token != payment.token || Self::_held_amount(&pending)? < amount {
+            return Err(PaymentError::InsufficientHeldFunds);
+        }
+
+        let customer = payment.customer;
+        let token_client = token::Client::new(&env, &pending.token);
+        token_client.transfer(&env.current_contract_address(), &customer, &amount);
+
+        let refunded_key = DataKey::Refunded(payment_id.clone());
+        env.storage().persistent().set(&refunded_key, &refunded);
+        env.storage().persistent().extend_ttl(&refunded_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+
+        pending.refund_amount = math::add(pending.refund_amount, amount)?;
+        pending_settlements.set(merchant.clone(), pending.clone());
+        env.storage().instance().set(&pending_key, &pending_settlements);
+
+        retail_events::publish(
+            &env,
+            retail_events::PAYMENT_REFUNDED,
+            &merchant,
+            PaymentRefundedEvent {
+                payment_id,
+                merchant: merchant.clone(),
+                customer,
+                token: pending.token.clone(),
+                currency: Symbol::new(&env, retail_events::TOKEN_CURRENCY),
+                amount,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+
+        // A failing dashboard must not block the refund
+        if let Some(dashboard) = Self::get_dashboard(env.clone()) {
+            let _ = DashboardClient::new(&env, &dashboard)
+                .try_record_event(&env.current_contract_address(), &MetricEvent::Refund(amount));
+        }
+
+        Ok(pending)
+    }
+
+    // Anyone may trigger a payout once the merchant's schedule has elapsed
+    pub fn settle_merchant(env: Env, merchant: Address) -> Result<SettlementReport, PaymentError> {
+        let pending_key = Symbol::new(&env, "PENDING_SETTLEMENTS");
+        let mut pending_settlements: Map<Address, PendingSettlement> = env
+            .storage()
+            .instance()
+            .get(&pending_key)
+            .unwrap_or(Map::new(&env));
+
+        let pending = pending_settlements
+            .get(merchant.clone())
+            .ok_or(PaymentError::SettlementNotConfigured)?;
+
+        // Empty batches would only grow the settlement history
+        if pending.payment_count == 0 {
+            return Err(PaymentError::NothingToSettle);
+        }
+
+        let config = Self::get_settlement_config(env.clone(), merchant.clone()).ok();
+        let (interval, fee_bps) = match &config {
+            Some(config) => (Self::_schedule_interval(config.schedule), config.fee_bps),
+            // Disabled merchants can flush what is still held without waiting or fees
+            None => (0, 0),
+        };
+
+        let now = env.ledger().timestamp();
+        if now < pending.period_start + interval {
+            return Err(PaymentError::SettlementNotDue);
+        }
+
+        // Transaction fees were already split off in process_payment; only the batch fee moves here
+        let settleable = Self::_held_amount(&pending)?;
+        let batch_fee = math::mul_div(settleable, fee_bps as i128, 10_000)?;
+        let net_amount = math::sub(settleable, batch_fee)?;
+
+        let token_client = token::Client::new(&env, &pending.token);
+        if batch_fee > 0 {
+            let fee_account = Self::_fee_account(&env)?;
+            token_client.transfer(&env.current_contract_address(), &fee_account, &batch_fee);
+
+            let mut fee_revenue = Self::_get_fee_revenue_entry(&env, &pending.token);
+            fee_revenue.settlement_fees = math::add(fee_revenue.settlement_fees, batch_fee)?;
+            Self::_set_fee_revenue_entry(&env, &fee_revenue);
+        }
+        if net_amount > 0 {
+            token_client.transfer(&env.current_contract_address(), &merchant, &net_amount);
+        }
+
+        let batch_key = Symbol::new(&env, "SETTLEMENT_COUNTER");
+        let batch_id = math::add_u32(env.storage().instance().get(&batch_key).unwrap_or(0), 1)?;
+        env.storage().instance().set(&batch_key, &batch_id);
+
+        let report = SettlementReport {
+            batch_id,
+            merchant: merchant.clone(),
+            token: pending.token.clone(),
+            period_start: pending.period_start,
+            period_end: now,
+            payment_count: pending.payment_count,
+            gross_amount: pending.gross_amount,
+            fee_amount: math::add(pending.fee_amount, batch_fee)?,
+            refund_amount: pending.refund_amount,
+            net_amount,
+        };
+
+        let report_key = DataKey::Settlement(batch_id);
+        env.storage().persistent().set(&report_key, &report);
+        env.storage().persistent().extend_ttl(&report_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Self::_append_index(&env, DataKey::MerchantSettlementCount(merchant.clone()), &batch_id, |position| {
+            DataKey::MerchantSettlement(merchant.clone(), position)
+        })?;
+
+        retail_events::publish(
+            &env,
+            retail_events::PAYMENT_SETTLED,
+            &merchant,
+            PaymentSettledEvent {
+                batch_id,
+                merchant: merchant.clone(),
+                token: report.token.clone(),
+                gross_amount: report.gross_amount,
+                fee_amount: report.fee_amount,
+                refund_amount: report.refund_amount,
+                net_amount: report.net_amount,
+                timestamp: now,
+            },
+        );
+
+        match config {
+            Some(config) => {
+                pending_settlements.set(merchant, Self::_new_pending_settlement(&env, &config));
+            }
+            None => {
+                pending_settlements.remove(merchant);
+            }
+        }
+        env.storage().instance().set(&pending_key, &pending_settlements);
+
+        Ok(report)
+    }
+
+    pub fn get_settlement_report(env: Env, batch_id: u32) -> Result<SettlementReport, PaymentError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Settlement(batch_id))
+            .ok_or(PaymentError::PaymentNotFound)
+    }
+
+    pub fn get_merchant_settlement_count(env: Env, merchant: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::MerchantSettlementCount(merchant)).unwrap_or(0)
+    }
+
+    // `limit` of the merchant's settlement reports from position `start` (see get_merchant_settlement_count)
+    pub fn get_merchant_settlements(env: Env, merchant: Address, start: u32, limit: u32) -> Vec<SettlementReport> {
+        merchant.require_auth();
+
+        let count = Self::get_merchant_settlement_count(env.clone(), merchant.clone());
+        let batch_ids: Vec<u32> = Self::_read_index(&env, count, start, limit, |position| {
+            DataKey::MerchantSettlement(merchant.clone(), position)
+        });
+
+        let mut merchant_settlements = Vec::new(&env);
+        for batch_id in batch_ids.iter() {
+            if let Ok(report) = Self::get_settlement_report(env.clone(), batch_id) {
+                merchant_settlements.push_back(report);
+            }
+        }
+        merchant_settlements
+    }
+
+    pub fn set_fee_schedule(
+        env: Env,
+        admin: Address,
+        scope: FeeScope,
+        schedule: FeeSchedule,
+    ) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        if schedule.percentage_bps > 10_000
+            || schedule.fixed_fee < 0
+            || schedule.min_fee < 0
+            || schedule.max_fee < 0
+            || (schedule.max_fee > 0 && schedule.max_fee < schedule.min_fee)
+        {
+            return Err(PaymentError::InvalidAmount);
+        }
+
+        let schedules_key = Symbol::new(&env, "FEE_SCHEDULES");
+        let mut fee_schedules: Map<FeeScope, FeeSchedule> = env
+            .storage()
+            .instance()
+            .get(&schedules_key)
+            .unwrap_or(Map::new(&env));
+
+        fee_schedules.set(scope, schedule);
+        env.storage().instance().set(&schedules_key, &fee_schedules);
+        Ok(())
+    }
+
+    pub fn remove_fee_schedule(env: Env, admin: Address, scope: FeeScope) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let schedules_key = Symbol::new(&env, "FEE_SCHEDULES");
+        let mut fee_schedules: Map<FeeScope, FeeSchedule> = env
+            .storage()
+            .instance()
+            .get(&schedules_key)
+            .unwrap_or(Map::new(&env));
+
+        fee_schedules.remove(scope);
+        env.storage().instance().set(&schedules_key, &fee_schedules);
+        Ok(())
+    }
+
+    pub fn get_fee_schedule(env: Env, merchant: Address, token: Address) -> Option<FeeSchedule> {
+        let fee_schedules: Map<FeeScope, FeeSchedule> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "FEE_SCHEDULES"))
+            .unwrap_or(Map::new(&env));
+
+        fee_schedules
+            .get(FeeScope::MerchantToken(merchant.clone(), token.clone()))
+            .or_else(|| fee_schedules.get(FeeScope::Merchant(merchant)))
+            .or_else(|| fee_schedules.get(FeeScope::Token(token)))
+            .or_else(|| fee_schedules.get(FeeScope::Default))
+    }
+
+    pub fn quote_fee(env: Env, merchant: Address, token: Address, amount: i128) -> Result<i128, PaymentError> {
+        let schedule = match Self::get_fee_schedule(env, merchant, token) {
+            Some(schedule) => schedule,
+            None => return Ok(0),
+        };
+
+        let mut fee = math::add(math::mul_div(amount, schedule.percentage_bps as i128, 10_000)?, schedule.fixed_fee)?;
+        if fee < schedule.min_fee {
+            fee = schedule.min_fee;
+        }
+        if schedule.max_fee > 0 && fee > schedule.max_fee {
+            fee = schedule.max_fee;
+        }
+
+        // The merchant must receive something after fees
+        if fee >= amount {
+            return Err(PaymentError::InvalidAmount);
+        }
+        Ok(fee)
+    }
+
+    pub fn get_fee_revenue(env: Env, caller: Address, token: Address) -> Result<FeeRevenueSummary, PaymentError> {
+        caller.require_auth();
+
+        if Self::_require_admin(&env, &caller).is_err() && !Self::is_auditor(env.clone(), caller) {
+            return Err(PaymentError::Unauthorized);
+        }
+
+        Ok(Self::_get_fee_revenue_entry(&env, &token))
+    }
+
+    fn _get_fee_revenue_entry(env: &Env, token: &Address) -> FeeRevenueSummary {
+        let fee_revenue: Map<Address, FeeRevenueSummary> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "FEE_REVENUE"))
+            .unwrap_or(Map::new(env));
+
+        fee_revenue.get(token.clone()).unwrap_or(FeeRevenueSummary {
+            token: token.clone(),
+            payment_count: 0,
+            gross_volume: 0,
+            transaction_fees: 0,
+            settlement_fees: 0,
+        })
+    }
+
+    fn _set_fee_revenue_entry(env: &Env, summary: &FeeRevenueSummary) {
+        let fee_revenue_key = Symbol::new(env, "FEE_REVENUE");
+        let mut fee_revenue: Map<Address, FeeRevenueSummary> = env
+            .storage()
+            .instance()
+            .get(&fee_revenue_key)
+            .unwrap_or(Map::new(env));
+
+        fee_revenue.set(summary.token.clone(), summary.clone());
+        env.storage().instance().set(&fee_revenue_key, &fee_revenue);
+    }
+
+    pub fn set_subscription_policy(
+        env: Env,
+        admin: Address,
+        policy: SubscriptionPolicy,
+    ) -> Result<(), PaymentError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        env.storage().instance().set(&Symbol::new(&env, "SUBSCRIPTION_POLICY"), &policy);
+        Ok(())
+    }
+
+    pub fn get_subscription_policy(env: Env) -> SubscriptionPolicy {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(&env, "SUBSCRIPTION_POLICY"))
+            .unwrap_or(SubscriptionPolicy {
+                grace_period: 3 * 86_400,
+                retry_interval: 86_400,
+                max_retries: 3,
+            })
+    }
+
+    // The customer must also approve this contract as spender on the token for the charges
+    pub fn create_subscription(
+        env: Env,
+        customer: Address,
+        merchant: Address,
+        token: Address,
+        amount: i128,
+        interval: u64,
+        max_total: i128,
+    ) -> Result<Subscription, PaymentError> {
+        customer.require_auth();
+
+        if amount <= 0 || interval == 0 || interval > MAX_SUBSCRIPTION_INTERVAL || max_total < 0 {
+            return Err(PaymentError::InvalidAmount);
+        }
+
+        let counter_key = Symbol::new(&env, "SUBSCRIPTION_COUNTER");
+        let subscription_id = math::add_u32(env.storage().instance().get(&counter_key).unwrap_or(0), 1)?;
+        env.storage().instance().set(&counter_key, &subscription_id);
+
+        let now = env.ledger().timestamp();
+        let subscription = Subscription {
+            subscription_id,
+            customer,
+            merchant,
+            token,
+            amount,
+            interval,
+            max_total,
+            total_charged: 0,
+            next_charge_at: now,
+            last_attempt_at: 0,
+            failed_attempts: 0,
+            status: SubscriptionStatus::Active,
+            created_at: now,
+        };
+
+        Self::_save_subscription(&env, &subscription);
+        Self::_append_index(&env, DataKey::CustomerSubscriptionCount(subscription.customer.clone()), &subscription_id, |position| {
+            DataKey::CustomerSubscription(subscription.customer.clone(), position)
+        })?;
+        Self::_append_index(&env, DataKey::MerchantSubscriptionCount(subscription.merchant.clone()), &subscription_id, |position| {
+            DataKey::MerchantSubscription(subscription.merchant.clone(), position)
+        })?;
+        Ok(subscription)
+    }
 
-// Kết quả: payment_id = "1678901234" (timestamp)
+    // Anyone may trigger a charge once it is due; failed pulls are recorded rather than reverted
+    pub fn charge_due(env: Env, subscription_id: u32) -> Result<Subscription, PaymentError> {
+        let mut subscription = Self::get_subscription(env.clone(), subscription_id)?;
+
+        if subscription.status != SubscriptionStatus::Active && subscription.status != SubscriptionStatus::PastDue {
+            return Err(PaymentError::SubscriptionInactive);
+        }
+
+        let policy = Self::get_subscription_policy(env.clone());
+        let now = env.ledger().timestamp();
+        if now < subscription.next_charge_at {
+            return Err(PaymentError::ChargeNotDue);
+        }
+        if subscription.status == SubscriptionStatus::PastDue
+            && now < subscription.last_attempt_at.checked_add(policy.retry_interval).ok_or(PaymentError::Overflow)?
+        {
+            return Err(PaymentError::ChargeNotDue);
+        }
+        let grace_ends_at = subscription
+            .next_charge_at
+            .checked_add(policy.grace_period)
+            .ok_or(PaymentError::Overflow)?;
+
+        if subscription.max_total > 0 && math::add(subscription.total_charged, subscription.amount)? > subscription.max_total {
+            subscription.status = SubscriptionStatus::Completed;
+            Self::_save_subscription(&env, &subscription);
+            return Ok(subscription);
+        }
+
+        // Validate the fee up front so a misconfigured schedule is not counted as a failed charge
+        Self::quote_fee(
+            env.clone(),
+            subscription.merchant.clone(),
+            subscription.token.clone(),
+            subscription.amount,
+        )?;
+
+        let contract_address = env.current_contract_address();
+        let token_client = token::Client::new(&env, &subscription.token);
+        let pulled = token_client.try_transfer_from(
+            &contract_address,
+            &subscription.customer,
+            &contract_address,
+            &subscription.amount,
+        );
+
+        subscription.last_attempt_at = now;
+        if matches!(pulled, Ok(Ok(()))) {
+            Self::_collect_payment(
+                &env,
+                &contract_address,
+                &subscription.customer,
+                &subscription.merchant,
+                &subscription.token,
+                subscription.amount,
+                "SUBSCRIPTION_",
+            )?;
+
+            subscription.total_charged = math::add(subscription.total_charged, subscription.amount)?;
+            subscription.next_charge_at = subscription
+                .next_charge_at
+                .checked_add(subscription.interval)
+                .ok_or(PaymentError::Overflow)?;
+            subscription.failed_attempts = 0;
+            subscription.status = SubscriptionStatus::Active;
+        } else {
+            // A pull is always attempted; the subscription only lapses once retries or the grace period run out
+            subscription.failed_attempts = math::add_u32(subscription.failed_attempts, 1)?;
+            subscription.status = if subscription.failed_attempts >= policy.max_retries || now > grace_ends_at {
+                SubscriptionStatus::Lapsed
+            } else {
+                SubscriptionStatus::PastDue
+            };
+        }
+
+        Self::_save_subscription(&env, &subscription);
+        Ok(subscription)
+    }
+
+    pub fn cancel_subscription(
+        env: Env,
+        caller: Address,
+        subscription_id: u32,
+    ) -> Result<Subscription, PaymentError> {
+        caller.require_auth();
+
+        let mut subscription = Self::get_subscription(env.clone(), subscription_id)?;
+
+        if caller != subscription.customer && caller != subscription.merchant {
+            return Err(PaymentError::Unauthorized);
+        }
+        if subscription.status != SubscriptionStatus::Active && subscription.status != SubscriptionStatus::PastDue {
+            return Err(PaymentError::SubscriptionInactive);
+        }
+
+        subscription.status = SubscriptionStatus::Cancelled;
+        Self::_save_subscription(&env, &subscription);
+        Ok(subscription)
+    }
+
+    pub fn get_subscription(env: Env, subscription_id: u32) -> Result<Subscription, PaymentError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Subscription(subscription_id))
+            .ok_or(PaymentError::SubscriptionNotFound)
+    }
+
+    pub fn get_customer_subscription_count(env: Env, customer: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::CustomerSubscriptionCount(customer)).unwrap_or(0)
+    }
+
+    // `limit` of the customer's subscriptions from position `start` (see get_customer_subscription_count)
+    pub fn get_customer_subscriptions(env: Env, customer: Address, start: u32, limit: u32) -> Vec<Subscription> {
+        let count = Self::get_customer_subscription_count(env.clone(), customer.clone());
+        let ids = Self::_read_index(&env, count, start, limit, |position| {
+            DataKey::CustomerSubscription(customer.clone(), position)
+        });
+        Self::_load_subscriptions(&env, ids)
+    }
+
+    pub fn get_merchant_subscription_count(env: Env, merchant: Address) -> u32 {
+        env.storage().persistent().get(&DataKey::MerchantSubscriptionCount(merchant)).unwrap_or(0)
+    }
+
+    // `limit` of the merchant's subscriptions from position `start` (see get_merchant_subscription_count)
+    pub fn get_merchant_subscriptions(env: Env, merchant: Address, start: u32, limit: u32) -> Vec<Subscription> {
+        let count = Self::get_merchant_subscription_count(env.clone(), merchant.clone());
+        let ids = Self::_read_index(&env, count, start, limit, |position| {
+            DataKey::MerchantSubscription(merchant.clone(), position)
+        });
+        Self::_load_subscriptions(&env, ids)
+    }
+
+    fn _save_subscription(env: &Env, subscription: &Subscription) {
+        let key = DataKey::Subscription(subscription.subscription_id);
+        env.storage().persistent().set(&key, subscription);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    fn _load_subscriptions(env: &Env, ids: Vec<u32>) -> Vec<Subscription> {
+        let mut result = Vec::new(env);
+        for id in ids.iter() {
+            if let Some(subscription) = env.storage().persistent().get(&DataKey::Subscription(id)) {
+                result.push_back(subscription);
+            }
+        }
+        result
+    }
+
+    fn _load_payments(env: &Env, ids: Vec<String>) -> Vec<PaymentRecord> {
+        let mut result = Vec::new(env);
+        for id in ids.iter() {
+            if let Some(payment) = env.storage().persistent().get(&DataKey::Payment(id)) {
+                result.push_back(payment);
+            }
+        }
+        result
+    }
+
+    // Stores `value` at the next position of an index whose length lives under `count_key`
+    fn _append_index<V: IntoVal<Env, Val>>(
+        env: &Env,
+        count_key: DataKey,
+        value: &V,
+        entry_key: impl Fn(u32) -> DataKey,
+    ) -> Result<(), PaymentError> {
+        let position: u32 = env.storage().persistent().get(&count_key).unwrap_or(0);
+        let key = entry_key(position);
+        env.storage().persistent().set(&key, value);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        env.storage().persistent().set(&count_key, &math::add_u32(position, 1)?);
+        env.storage().persistent().extend_ttl(&count_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Ok(())
+    }
+
+    // Entries start..start + limit of an index holding `count` entries
+    fn _read_index<V: TryFromVal<Env, Val> + IntoVal<Env, Val>>(
+        env: &Env,
+        count: u32,
+        start: u32,
+        limit: u32,
+        entry_key: impl Fn(u32) -> DataKey,
+    ) -> Vec<V> {
+        let end = count.min(start.saturating_add(limit));
+        let mut result = Vec::new(env);
+        for position in start..end {
+            if let Some(value) = env.storage().persistent().get(&entry_key(position)) {
+                result.push_back(value);
+            }
+        }
+        result
+    }
+
+    // Splits off fees and routes the remainder to the merchant or the settlement hold
+    fn _collect_payment(
+        env: &Env,
+        payer: &Address,
+        customer: &Address,
+        merchant: &Address,
+        token: &Address,
+        amount: i128,
+        payment_id_prefix: &str,
+    ) -> Result<PaymentRecord, PaymentError> {
+        let fee_amount = Self::quote_fee(env.clone(), merchant.clone(), token.clone(), amount)?;
+        let merchant_amount = math::sub(amount, fee_amount)?;
+
+        // Transfer tokens - held in the contract when the merchant settles in batches
+        let token_client = token::Client::new(env, token);
+        if fee_amount > 0 {
+            let fee_account = Self::_fee_account(env)?;
+            token_client.transfer(payer, &fee_account, &fee_amount);
+        }
+
+        let settlement_configs: Map<Address, SettlementConfig> = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(env, "SETTLEMENT_CONFIGS"))
+            .unwrap_or(Map::new(env));
+
+        match settlement_configs.get(merchant.clone()) {
+            Some(config) if config.token == *token => {
+                // Subscription charges are already pulled into the contract
+                if *payer != env.current_contract_address() {
+                    token_client.transfer(payer, &env.current_contract_address(), &merchant_amount);
+                }
+
+                let pending_key = Symbol::new(env, "PENDING_SETTLEMENTS");
+                let mut pending_settlements: Map<Address, PendingSettlement> = env
+                    .storage()
+                    .instance()
+                    .get(&pending_key)
+                    .unwrap_or(Map::new(env));
+
+                let mut pending = pending_settlements
+                    .get(merchant.clone())
+                    .unwrap_or(Self::_new_pending_settlement(env, &config));
+                pending.payment_count = math::add_u32(pending.payment_count, 1)?;
+                pending.gross_amount = math::add(pending.gross_amount, amount)?;
+                pending.fee_amount = math::add(pending.fee_amount, fee_amount)?;
+
+                pending_settlements.set(merchant.clone(), pending);
+                env.storage().instance().set(&pending_key, &pending_settlements);
+            }
+            _ => {
+                token_client.transfer(payer, merchant, &merchant_amount);
+            }
+        }
+
+        let mut fee_revenue = Self::_get_fee_revenue_entry(env, token);
+        fee_revenue.payment_count = math::add_u32(fee_revenue.payment_count, 1)?;
+        fee_revenue.gross_volume = math::add(fee_revenue.gross_volume, amount)?;
+        fee_revenue.transaction_fees = math::add(fee_revenue.transaction_fees, fee_amount)?;
+        Self::_set_fee_revenue_entry(env, &fee_revenue);
+
+        // Create payment record
+        let timestamp = env.ledger().timestamp();
+        let counter_key = Symbol::new(env, "PAYMENT_COUNTER");
+        let payment_number: u32 = math::add_u32(env.storage().instance().get(&counter_key).unwrap_or(0), 1)?;
+        env.storage().instance().set(&counter_key, &payment_number);
+        let payment_id = Self::_numbered_id(env, payment_id_prefix, payment_number);
 
         let record = PaymentRecord {
             payment_id,
             customer: customer.clone(),
             merchant: merchant.clone(),
             amount,
+            token: token.clone(),
+            fee_amount,
             timestamp,
         };
 
-        // Store payment record
-        let payments_key = Symbol::new(&env, "PAYMENTS");
-        let mut payments: Vec<PaymentRecord> = env
-            .storage()
-            .instance()
-            .get(&payments_key)
-            .unwrap_or(Vec::new(&env));
-        
-        payments.push_back(record.clone());
-        env.storage().instance().set(&payments_key, &payments);
+        // Store payment record, then index it by position overall, per customer and per merchant
+        let payment_key = DataKey::Payment(record.payment_id.clone());
+        env.storage().persistent().set(&payment_key, &record);
+        env.storage().persistent().extend_ttl(&payment_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+
+        let position_key = DataKey::PaymentAt(payment_number - 1);
+        env.storage().persistent().set(&position_key, &record.payment_id);
+        env.storage().persistent().extend_ttl(&position_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+        Self::_append_index(env, DataKey::CustomerPaymentCount(customer.clone()), &record.payment_id, |position| {
+            DataKey::CustomerPayment(customer.clone(), position)
+        })?;
+        Self::_append_index(env, DataKey::MerchantPaymentCount(merchant.clone()), &record.payment_id, |position| {
+            DataKey::MerchantPayment(merchant.clone(), position)
+        })?;
+
+        retail_events::publish(
+            env,
+            retail_events::PAYMENT_CREATED,
+            merchant,
+            PaymentCreatedEvent {
+                payment_id: record.payment_id.clone(),
+                customer: record.customer.clone(),
+                merchant: record.merchant.clone(),
+                token: record.token.clone(),
+                currency: Symbol::new(env, retail_events::TOKEN_CURRENCY),
+                amount: record.amount,
+                fee_amount: record.fee_amount,
+                timestamp: record.timestamp,
+            },
+        );
 
         Ok(record)
     }
 
-    pub fn get_payment_history(env: Env, customer: Address) -> Vec<PaymentRecord> {
-        let payments_key = Symbol::new(&env, "PAYMENTS");
-        let all_payments: Vec<PaymentRecord> = env
-            .storage()
-            .instance()
-            .get(&payments_key)
-            .unwrap_or(Vec::new(&env));
-        
-        let mut customer_payments = Vec::new(&env);
-        
-        for i in 0..all_payments.len() {
-            let payment = all_payments.get(i).unwrap();
-            if payment.customer == customer {
-                customer_payments.push_back(payment.clone());
+    fn _new_pending_settlement(env: &Env, config: &SettlementConfig) -> PendingSettlement {
+        PendingSettlement {
+            merchant: config.merchant.clone(),
+            token: config.token.clone(),
+            period_start: env.ledger().timestamp(),
+            payment_count: 0,
+            gross_amount: 0,
+            fee_amount: 0,
+            refund_amount: 0,
+        }
+    }
+
+    // `prefix` followed by `number` in decimal, e.g. PAYMENT_42
+    fn _numbered_id(env: &Env, prefix: &str, number: u32) -> String {
+        let mut buffer = [0u8; 32];
+        let prefix_len = prefix.len();
+        buffer[..prefix_len].copy_from_slice(prefix.as_bytes());
+
+        let mut digits = [0u8; 10];
+        let mut digit_count = 0;
+        let mut remaining = number;
+        loop {
+            digits[digit_count] = b'0' + (remaining % 10) as u8;
+            digit_count += 1;
+            remaining /= 10;
+            if remaining == 0 {
+                break;
             }
         }
-        
-        customer_payments
+        for i in 0..digit_count {
+            buffer[prefix_len + i] = digits[digit_count - 1 - i];
+        }
+
+        String::from_bytes(env, &buffer[..prefix_len + digit_count])
     }
 
-    pub fn get_all_payments(env: Env, admin: Address) -> Result<Vec<PaymentRecord>, PaymentError> {
-        admin.require_auth();
-        
-        let payments_key = Symbol::new(&env, "PAYMENTS");
-        let payments: Vec<PaymentRecord> = env
+    fn _held_amount(pending: &PendingSettlement) -> Result<i128, PaymentError> {
+        Ok(math::sub(math::sub(pending.gross_amount, pending.fee_amount)?, pending.refund_amount)?)
+    }
+
+    fn _fee_account(env: &Env) -> Result<Address, PaymentError> {
+        env.storage()
+            .instance()
+            .get(&Symbol::new(env, "FEE_ACCOUNT"))
+            .ok_or(PaymentError::FeeAccountNotSet)
+    }
+
+    fn _schedule_interval(schedule: PayoutSchedule) -> u64 {
+        match schedule {
+            PayoutSchedule::Daily => 86_400,
+            PayoutSchedule::Weekly => 7 * 86_400,
+        }
+    }
+
+    fn _require_admin(env: &Env, address: &Address) -> Result<(), PaymentError> {
+        let admin: Address = env
             .storage()
             .instance()
-            .get(&payments_key)
-            .unwrap_or(Vec::new(&env));
-            
-        Ok(payments)
+            .get(&Symbol::new(env, "ADMIN"))
+            .ok_or(PaymentError::NotInitialized)?;
+
+        if *address != admin {
+            return Err(PaymentError::Unauthorized);
+        }
+        Ok(())
     }
 
     pub fn hello(env: Env) -> String {
         String::from_str(&env, "Hello from PaymentProcessor!")
     }
-} 
\ No newline at end of file
+} 
+
+mod test;
\ No newline at end of file
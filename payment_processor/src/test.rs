@@ -0,0 +1,223 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{
+    testutils::{Address as _, Ledger},
+    token::{StellarAssetClient, TokenClient},
+    Env,
+};
+
+struct Setup<'a> {
+    processor: PaymentProcessorClient<'a>,
+    processor_id: Address,
+    token: TokenClient<'a>,
+    admin: Address,
+    customer: Address,
+    merchant: Address,
+    fee_account: Address,
+}
+
+fn setup(env: &Env) -> Setup<'_> {
+    env.mock_all_auths();
+
+    let admin = Address::generate(env);
+    let customer = Address::generate(env);
+    let merchant = Address::generate(env);
+    let fee_account = Address::generate(env);
+
+    let token_id = env.register_stellar_asset_contract_v2(admin.clone()).address();
+    StellarAssetClient::new(env, &token_id).mint(&customer, &1_000_000);
+
+    let processor_id = env.register_contract(None, PaymentProcessor);
+    let processor = PaymentProcessorClient::new(env, &processor_id);
+    processor.initialize(&admin);
+    processor.set_fee_account(&admin, &fee_account);
+
+    Setup {
+        processor,
+        processor_id,
+        token: TokenClient::new(env, &token_id),
+        admin,
+        customer,
+        merchant,
+        fee_account,
+    }
+}
+
+#[test]
+fn test_admin_and_auditor_roles() {
+    let env = Env::default();
+    let s = setup(&env);
+    let auditor = Address::generate(&env);
+    let stranger = Address::generate(&env);
+    s.processor.process_payment(&s.customer, &s.merchant, &10_000, &s.token.address);
+
+    assert!(s.processor.try_initialize(&stranger).is_err());
+    assert!(s.processor.try_add_auditor(&stranger, &auditor).is_err());
+    s.processor.add_auditor(&s.admin, &auditor);
+    assert!(s.processor.is_auditor(&auditor));
+
+    // Admin and auditors read everything, strangers nothing
+    assert_eq!(s.processor.get_all_payments(&s.admin, &0, &10).len(), 1);
+    assert_eq!(s.processor.get_all_payments(&auditor, &0, &10).len(), 1);
+    assert!(s.processor.try_get_all_payments(&stranger, &0, &10).is_err());
+    assert_eq!(s.processor.get_fee_revenue(&auditor, &s.token.address).payment_count, 1);
+    assert!(s.processor.try_get_fee_revenue(&stranger, &s.token.address).is_err());
+
+    // Auditors cannot write
+    assert!(s.processor.try_set_fee_account(&auditor, &auditor).is_err());
+
+    s.processor.remove_auditor(&s.admin, &auditor);
+    assert!(s.processor.try_get_all_payments(&auditor, &0, &10).is_err());
+
+    let new_admin = Address::generate(&env);
+    s.processor.transfer_admin(&s.admin, &new_admin);
+    assert_eq!(s.processor.get_admin(), new_admin);
+    assert!(s.processor.try_get_all_payments(&s.admin, &0, &10).is_err());
+    assert_eq!(s.processor.get_all_payments(&new_admin, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_settlement_batches() {
+    let env = Env::default();
+    let s = setup(&env);
+    let token = s.token.address.clone();
+    s.processor.set_fee_schedule(
+        &s.admin,
+        &FeeScope::Default,
+        &FeeSchedule { percentage_bps: 200, fixed_fee: 0, min_fee: 0, max_fee: 0 },
+    );
+    s.processor.configure_settlement(&s.admin, &s.merchant, &token, &PayoutSchedule::Daily, &100);
+
+    // 2% transaction fee goes out at once, the rest is held
+    let payment = s.processor.process_payment(&s.customer, &s.merchant, &10_000, &token);
+    assert_eq!(payment.fee_amount, 200);
+    assert_eq!(s.token.balance(&s.processor_id), 9_800);
+    assert!(s.processor.try_settle_merchant(&s.merchant).is_err());
+
+    // Refunds are tied to one payment and capped at its amount
+    let other_merchant = Address::generate(&env);
+    assert!(s.processor.try_refund_held_payment(&other_merchant, &payment.payment_id, &1_000).is_err());
+    assert!(s.processor.try_refund_held_payment(&s.merchant, &payment.payment_id, &10_001).is_err());
+    s.processor.refund_held_payment(&s.merchant, &payment.payment_id, &1_000);
+    assert_eq!(s.token.balance(&s.customer), 991_000);
+    assert_eq!(s.processor.get_refunded_amount(&payment.payment_id), 1_000);
+
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    let report = s.processor.settle_merchant(&s.merchant);
+
+    // Held 8_800, 1% batch fee 88
+    assert_eq!(report.payment_count, 1);
+    assert_eq!(report.gross_amount, 10_000);
+    assert_eq!(report.fee_amount, 288);
+    assert_eq!(report.refund_amount, 1_000);
+    assert_eq!(report.net_amount, 8_712);
+    assert_eq!(report.gross_amount - report.fee_amount - report.refund_amount, report.net_amount);
+    assert_eq!(s.token.balance(&s.merchant), 8_712);
+    assert_eq!(s.token.balance(&s.fee_account), 288);
+    assert_eq!(s.token.balance(&s.processor_id), 0);
+    assert_eq!(s.processor.get_settlement_report(&report.batch_id), report);
+
+    // Empty periods do not produce batches
+    env.ledger().with_mut(|li| li.timestamp += 86_400);
+    assert!(s.processor.try_settle_merchant(&s.merchant).is_err());
+    assert_eq!(s.processor.get_merchant_settlements(&s.merchant, &0, &10).len(), 1);
+}
+
+#[test]
+fn test_quote_fee_boundaries() {
+    let env = Env::default();
+    let s = setup(&env);
+    let token = s.token.address.clone();
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &10_000), 0);
+
+    let schedule = FeeSchedule { percentage_bps: 100, fixed_fee: 5, min_fee: 50, max_fee: 500 };
+    s.processor.set_fee_schedule(&s.admin, &FeeScope::Default, &schedule);
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &10_000), 105);
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &1_000), 50);
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &100_000), 500);
+
+    // The merchant must keep something
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &51), 50);
+    assert!(s.processor.try_quote_fee(&s.merchant, &token, &50).is_err());
+    assert!(s.processor.try_process_payment(&s.customer, &s.merchant, &50, &token).is_err());
+
+    // More specific scopes win
+    let flat = FeeSchedule { percentage_bps: 0, fixed_fee: 1, min_fee: 0, max_fee: 0 };
+    s.processor.set_fee_schedule(&s.admin, &FeeScope::Merchant(s.merchant.clone()), &flat);
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &10_000), 1);
+    let free = FeeSchedule { percentage_bps: 0, fixed_fee: 0, min_fee: 0, max_fee: 0 };
+    s.processor.set_fee_schedule(&s.admin, &FeeScope::MerchantToken(s.merchant.clone(), token.clone()), &free);
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &10_000), 0);
+    s.processor.remove_fee_schedule(&s.admin, &FeeScope::MerchantToken(s.merchant.clone(), token.clone()));
+    assert_eq!(s.processor.quote_fee(&s.merchant, &token, &10_000), 1);
+
+    let too_high = FeeSchedule { percentage_bps: 10_001, fixed_fee: 0, min_fee: 0, max_fee: 0 };
+    assert!(s.processor.try_set_fee_schedule(&s.admin, &FeeScope::Default, &too_high).is_err());
+    let inverted = FeeSchedule { percentage_bps: 0, fixed_fee: 0, min_fee: 100, max_fee: 10 };
+    assert!(s.processor.try_set_fee_schedule(&s.admin, &FeeScope::Default, &inverted).is_err());
+    assert!(s.processor.try_set_fee_schedule(&s.merchant, &FeeScope::Default, &free).is_err());
+}
+
+#[test]
+fn test_subscription_lifecycle() {
+    let env = Env::default();
+    let s = setup(&env);
+    let token = s.token.address.clone();
+    let day = 86_400;
+    env.ledger().with_mut(|li| li.timestamp = 10 * day);
+
+    assert!(s.processor.try_create_subscription(&s.customer, &s.merchant, &token, &1_000, &0, &0).is_err());
+    assert!(s.processor.try_create_subscription(&s.customer, &s.merchant, &token, &1_000, &u64::MAX, &0).is_err());
+
+    // Active: charged on schedule until max_total is reached, then Completed
+    s.token.approve(&s.customer, &s.processor_id, &10_000, &1_000);
+    let capped = s.processor.create_subscription(&s.customer, &s.merchant, &token, &1_000, &(30 * day), &2_000);
+    let charged = s.processor.charge_due(&capped.subscription_id);
+    assert_eq!(charged.status, SubscriptionStatus::Active);
+    assert_eq!(charged.total_charged, 1_000);
+    assert_eq!(charged.next_charge_at, 40 * day);
+    assert!(s.processor.try_charge_due(&capped.subscription_id).is_err());
+
+    // A keeper arriving after the grace period still charges instead of lapsing
+    env.ledger().with_mut(|li| li.timestamp = 50 * day);
+    assert_eq!(s.processor.charge_due(&capped.subscription_id).total_charged, 2_000);
+    env.ledger().with_mut(|li| li.timestamp = 70 * day);
+    assert_eq!(s.processor.charge_due(&capped.subscription_id).status, SubscriptionStatus::Completed);
+    assert!(s.processor.try_charge_due(&capped.subscription_id).is_err());
+    assert_eq!(s.token.balance(&s.merchant), 2_000);
+
+    // PastDue: failed pulls retry after retry_interval and lapse after max_retries
+    s.token.approve(&s.customer, &s.processor_id, &0, &1_000);
+    let unfunded = s.processor.create_subscription(&s.customer, &s.merchant, &token, &1_000, &(30 * day), &0);
+    let failed = s.processor.charge_due(&unfunded.subscription_id);
+    assert_eq!(failed.status, SubscriptionStatus::PastDue);
+    assert_eq!(failed.failed_attempts, 1);
+    assert!(s.processor.try_charge_due(&unfunded.subscription_id).is_err());
+    env.ledger().with_mut(|li| li.timestamp += day);
+    assert_eq!(s.processor.charge_due(&unfunded.subscription_id).status, SubscriptionStatus::PastDue);
+    env.ledger().with_mut(|li| li.timestamp += day);
+    let lapsed = s.processor.charge_due(&unfunded.subscription_id);
+    assert_eq!(lapsed.status, SubscriptionStatus::Lapsed);
+    assert_eq!(lapsed.failed_attempts, 3);
+    assert!(s.processor.try_cancel_subscription(&s.customer, &unfunded.subscription_id).is_err());
+
+    // Cancel: only the customer or merchant, and only while live
+    let cancelled = s.processor.create_subscription(&s.customer, &s.merchant, &token, &1_000, &(30 * day), &0);
+    assert!(s.processor.try_cancel_subscription(&Address::generate(&env), &cancelled.subscription_id).is_err());
+    assert_eq!(
+        s.processor.cancel_subscription(&s.merchant, &cancelled.subscription_id).status,
+        SubscriptionStatus::Cancelled
+    );
+    assert!(s.processor.try_charge_due(&cancelled.subscription_id).is_err());
+    assert!(s.processor.try_cancel_subscription(&s.customer, &cancelled.subscription_id).is_err());
+
+    // Each subscription is indexed for both parties and pages by position
+    assert_eq!(s.processor.get_customer_subscription_count(&s.customer), 3);
+    let page = s.processor.get_merchant_subscriptions(&s.merchant, &1, &10);
+    assert_eq!(page.len(), 2);
+    assert_eq!(page.get(1).unwrap().status, SubscriptionStatus::Cancelled);
+    assert_eq!(s.processor.get_customer_subscriptions(&s.customer, &0, &1).get(0).unwrap(), s.processor.get_subscription(&capped.subscription_id));
+    assert_eq!(s.processor.get_customer_payment_count(&s.customer), 2);
+    assert_eq!(s.processor.get_payment_history(&s.customer, &1, &10).len(), 1);
+}
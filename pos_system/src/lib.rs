@@ -3,6 +3,8 @@ use soroban_sdk::{
     contract, contractimpl, contracttype, 
     Env, String, Address, Vec, Symbol, Map, Error
 };
+use retail_events::SaleCreatedEvent;
+use shared::{math, DashboardClient, MetricEvent};
 
 #[contract]
 pub struct PosSystem;
@@ -47,6 +49,27 @@ impl PosSystem {
         env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
     }
 
+    // Sales are reported to this dashboard as they happen
+    pub fn set_dashboard(env: Env, admin: Address, dashboard: Address) -> Result<(), Error> {
+        admin.require_auth();
+        let stored_admin: Address = env
+            .storage()
+            .instance()
+            .get(&Symbol::new(&env, "ADMIN"))
+            .ok_or(Error::from_contract_error(1003))?;
+        if admin != stored_admin {
+            return Err(Error::from_contract_error(1003)); // Unauthorized
+        }
+
+        env.storage().instance().set(&Symbol::new(&env, "DASHBOARD"), &dashboard);
+        Ok(())
+    }
+
+    pub fn get_dashboard(env: Env) -> Option<Address> {
+        env.storage().instance().get(&Symbol::new(&env, "DASHBOARD"))
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn create_sale(
         env: Env,
         cashier: Address,
@@ -63,13 +86,13 @@ impl PosSystem {
         let mut total_amount: i128 = 0;
         for i in 0..items.len() {
             let item = items.get(i).unwrap();
-            total_amount += item.total_price;
+            total_amount = math::add(total_amount, item.total_price)?;
             
             // Check inventory availability (mock)
             Self::_check_inventory_stock(env.clone(), _inventory_manager.clone(), item.sku.clone(), item.quantity)?;
         }
 
-        let final_amount = if discount > 0 { total_amount - discount } else { total_amount };
+        let final_amount = if discount > 0 { math::sub(total_amount, discount)? } else { total_amount };
 
         if final_amount <= 0 {
             return Err(Error::from_contract_error(1001)); // Invalid amount
@@ -98,7 +121,7 @@ impl PosSystem {
 
         // Tạo sale ID đơn giản không dùng format!
         let timestamp = env.ledger().timestamp();
-        let sale_id = if timestamp % 2 == 0 {
+        let sale_id = if timestamp.is_multiple_of(2) {
             String::from_str(&env, "SALE_EVEN")
         } else {
             String::from_str(&env, "SALE_ODD")
@@ -128,11 +151,27 @@ impl PosSystem {
         env.storage().instance().set(&sales_key, &sales);
 
         // Emit sale event
-        env.events().publish(
-            (Symbol::new(&env, "sale_created"), cashier),
-            sale.clone()
+        retail_events::publish(
+            &env,
+            retail_events::SALE_CREATED,
+            &cashier,
+            SaleCreatedEvent {
+                sale_id: sale.sale_id.clone(),
+                cashier: cashier.clone(),
+                customer: sale.customer.clone(),
+                total_amount,
+                discount,
+                final_amount,
+                timestamp,
+            },
         );
 
+        // A failing dashboard must not block the sale
+        if let Some(dashboard) = Self::get_dashboard(env.clone()) {
+            let _ = DashboardClient::new(&env, &dashboard)
+                .try_record_event(&env.current_contract_address(), &MetricEvent::Sale(final_amount));
+        }
+
         Ok(sale)
     }
 
@@ -233,10 +272,10 @@ impl PosSystem {
             .get(&Symbol::new(&env, "SALES"))
             .unwrap_or_else(|| Vec::new(&env));
         
-        sales.len() as u32
+        sales.len()
     }
 
-    pub fn get_total_revenue(env: Env) -> i128 {
+    pub fn get_total_revenue(env: Env) -> Result<i128, Error> {
         let sales: Vec<Sale> = env
             .storage()
             .instance()
@@ -246,12 +285,12 @@ impl PosSystem {
         let mut total: i128 = 0;
         for i in 0..sales.len() {
             let sale = sales.get(i).unwrap();
-            total += sale.final_amount;
+            total = math::add(total, sale.final_amount)?;
         }
-        total
+        Ok(total)
     }
 
-    pub fn get_payment_method_stats(env: Env) -> Map<PaymentMethod, i128> {
+    pub fn get_payment_method_stats(env: Env) -> Result<Map<PaymentMethod, i128>, Error> {
         let sales: Vec<Sale> = env
             .storage()
             .instance()
@@ -268,11 +307,11 @@ impl PosSystem {
         for i in 0..sales.len() {
             let sale = sales.get(i).unwrap();
             match sale.payment_method {
-                PaymentMethod::Cash => cash_total += sale.final_amount,
-                PaymentMethod::CreditCard => card_total += sale.final_amount,
-                PaymentMethod::BankTransfer => transfer_total += sale.final_amount,
-                PaymentMethod::Crypto => crypto_total += sale.final_amount,
-                PaymentMethod::LoyaltyPoints => loyalty_total += sale.final_amount,
+                PaymentMethod::Cash => cash_total = math::add(cash_total, sale.final_amount)?,
+                PaymentMethod::CreditCard => card_total = math::add(card_total, sale.final_amount)?,
+                PaymentMethod::BankTransfer => transfer_total = math::add(transfer_total, sale.final_amount)?,
+                PaymentMethod::Crypto => crypto_total = math::add(crypto_total, sale.final_amount)?,
+                PaymentMethod::LoyaltyPoints => loyalty_total = math::add(loyalty_total, sale.final_amount)?,
             }
         }
 
@@ -282,7 +321,7 @@ impl PosSystem {
         stats.set(PaymentMethod::Crypto, crypto_total);
         stats.set(PaymentMethod::LoyaltyPoints, loyalty_total);
 
-        stats
+        Ok(stats)
     }
 
     pub fn hello(env: Env) -> String {
@@ -0,0 +1,216 @@
+#![no_std]
+use soroban_sdk::{contracttype, Address, Env, IntoVal, String, Symbol, Val};
+
+// Every contract event uses the same topic layout so the indexer can route on it:
+//   topics = (event_name: Symbol, version: u32, subject: Address)
+//   data   = one of the *Event structs below
+// Bump EVENT_VERSION whenever a payload struct changes shape.
+// Token movements (transfer, mint, burn, approve) instead use the SEP-41 layouts below.
+pub const EVENT_VERSION: u32 = 1;
+
+pub const PAYMENT_CREATED: &str = "payment_created";
+pub const PAYMENT_REFUNDED: &str = "payment_refunded";
+pub const PAYMENT_SETTLED: &str = "payment_settled";
+pub const PAYMENT_CONFIRMED: &str = "payment_confirmed";
+pub const PAYMENT_FAILED: &str = "payment_failed";
+pub const SALE_CREATED: &str = "sale_created";
+pub const PRODUCT_ADDED: &str = "product_added";
+pub const STOCK_UPDATED: &str = "stock_updated";
+pub const POINTS_EARNED: &str = "points_earned";
+pub const POINTS_REDEEMED: &str = "points_redeemed";
+pub const POINTS_EXPIRED: &str = "points_expired";
+pub const POINTS_ADJUSTED: &str = "points_adjusted";
+pub const POINTS_TRANSFERRED: &str = "points_transferred";
+pub const SET_ADMIN: &str = "set_admin";
+pub const CLAWBACK: &str = "clawback";
+pub const CONVERT: &str = "convert";
+pub const ISSUER_ADDED: &str = "issuer_added";
+pub const ISSUER_REVOKED: &str = "issuer_revoked";
+pub const TRANSFER_BLOCKED: &str = "transfer_blocked";
+pub const CURRENCY_TRANSFER: &str = "currency_transfer";
+pub const CURRENCY_MINT: &str = "currency_mint";
+pub const CURRENCY_BURN: &str = "currency_burn";
+pub const ALERT_RAISED: &str = "alert_raised"; // data is the dashboard Alert
+pub const ALERT_ACKNOWLEDGED: &str = "alert_acknowledged";
+
+// Payment currency when the payment's `token` address is itself the asset
+pub const TOKEN_CURRENCY: &str = "SEP41";
+
+// Decode the topic vector of any event into this tuple
+pub type EventTopics = (Symbol, u32, Address);
+
+// SEP-41 token events; data is the i128 amount unless noted
+pub const TRANSFER: &str = "transfer";
+pub const MINT: &str = "mint";
+pub const BURN: &str = "burn";
+pub const APPROVE: &str = "approve";
+
+pub type TransferTopics = (Symbol, Address, Address); // ("transfer", from, to)
+pub type MintTopics = (Symbol, Address, Address); // ("mint", admin, to)
+pub type BurnTopics = (Symbol, Address); // ("burn", from)
+pub type ApproveTopics = (Symbol, Address, Address); // ("approve", from, spender), data (amount, expiration_ledger: u32)
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentCreatedEvent {
+    pub payment_id: String,
+    pub customer: Address,
+    pub merchant: Address,
+    pub token: Address,
+    pub currency: Symbol, // xmoney_token currency such as "USDC", else TOKEN_CURRENCY
+    pub amount: i128,
+    pub fee_amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentRefundedEvent {
+    pub payment_id: String,
+    pub merchant: Address,
+    pub customer: Address,
+    pub token: Address,
+    pub currency: Symbol,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+// Payload for payment_confirmed and payment_failed
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentUpdatedEvent {
+    pub payment_id: String,
+    pub customer: Address,
+    pub merchant: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PaymentSettledEvent {
+    pub batch_id: u32,
+    pub merchant: Address,
+    pub token: Address,
+    pub gross_amount: i128,
+    pub fee_amount: i128,
+    pub refund_amount: i128,
+    pub net_amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SaleCreatedEvent {
+    pub sale_id: String,
+    pub cashier: Address,
+    pub customer: Option<Address>,
+    pub total_amount: i128,
+    pub discount: i128,
+    pub final_amount: i128,
+    pub timestamp: u64,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProductAddedEvent {
+    pub sku: String,
+    pub name: String,
+    pub price: i128,
+    pub initial_stock: u32,
+    pub supplier: Address,
+    pub location: String,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct StockUpdatedEvent {
+    pub sku: String,
+    pub new_quantity: u32,
+}
+
+// Payload for every points_* event; subject is the account whose balance moved
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PointsMovedEvent {
+    pub tx_id: u64,
+    pub amount: i128, // signed change applied to the balance
+    pub balance_after: i128,
+    pub reason: String,
+    pub reference_id: String,
+    pub timestamp: u64,
+}
+
+// Subject is the new admin
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct AdminChangedEvent {
+    pub previous_admin: Address,
+    pub timestamp: u64,
+}
+
+// Subject is the account the tokens were taken from
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClawbackEvent {
+    pub admin: Address,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+// Subject is the holder; currencies are their symbols, e.g. "USDC"
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct ConvertEvent {
+    pub from_currency: Symbol,
+    pub to_currency: Symbol,
+    pub amount_in: i128,
+    pub amount_out: i128,
+    pub timestamp: u64,
+}
+
+// Payload for currency_transfer of the xmoney_token side currencies; subject is the sender
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurrencyTransferEvent {
+    pub to: Address,
+    pub currency: Symbol,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+// Payload for currency_mint and currency_burn; subject is the account credited or debited
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct CurrencySupplyEvent {
+    pub currency: Symbol,
+    pub amount: i128,
+    pub timestamp: u64,
+}
+
+// Payload for issuer_added and issuer_revoked; subject is the issuer, daily_cap is 0 once revoked
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct IssuerUpdatedEvent {
+    pub daily_cap: i128,
+    pub timestamp: u64,
+}
+
+// Subject is the account
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct TransferBlockedEvent {
+    pub blocked: bool,
+    pub timestamp: u64,
+}
+
+// Publishes `data` under the standard (name, version, subject) topics
+pub fn publish<D>(env: &Env, name: &str, subject: &Address, data: D)
+where
+    D: IntoVal<Env, Val>,
+{
+    let topics: EventTopics = (Symbol::new(env, name), EVENT_VERSION, subject.clone());
+    env.events().publish(topics, data);
+}
+
+mod test;
@@ -0,0 +1,38 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{contract, contractimpl, testutils::{Address as _, Events}, IntoVal};
+
+// Stand-in emitter, since events can only be published from inside a contract
+#[contract]
+struct Emitter;
+
+#[contractimpl]
+impl Emitter {
+    pub fn block(env: Env, account: Address) {
+        publish(
+            &env,
+            TRANSFER_BLOCKED,
+            &account,
+            TransferBlockedEvent {
+                blocked: true,
+                timestamp: env.ledger().timestamp(),
+            },
+        );
+    }
+}
+
+#[test]
+fn test_publish_uses_standard_topics() {
+    let env = Env::default();
+    let emitter = env.register_contract(None, Emitter);
+    let account = Address::generate(&env);
+    EmitterClient::new(&env, &emitter).block(&account);
+
+    let (contract, topics, data) = env.events().all().last().unwrap();
+    assert_eq!(contract, emitter);
+    let expected: EventTopics = (Symbol::new(&env, TRANSFER_BLOCKED), EVENT_VERSION, account);
+    assert_eq!(topics, expected.into_val(&env));
+    let data: TransferBlockedEvent = data.into_val(&env);
+    assert!(data.blocked);
+}
@@ -1,5 +1,7 @@
 #![no_std]
-use soroban_sdk::{contracttype, Address, String};
+use soroban_sdk::{contractclient, contracttype, Address, Env, String, Val};
+
+pub mod math;
 
 #[contracttype]
 #[derive(Clone, Debug, PartialEq)]
@@ -22,6 +24,67 @@ pub struct Employee {
     pub active: bool,
 }
 
+// Mirrors employee_manager::Permission so other contracts can check staff permissions
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Permission {
+    ManageEmployees,
+    ProcessPayments,
+    ManageInventory,
+    ViewReports,
+    ManageVendors,
+    IssueLoyaltyPoints,
+    ProcessPayroll,
+    SystemAdmin,
+    FulfillRedemptions,
+}
+
+// The part of employee_manager that other contracts call into
+#[contractclient(name = "EmployeeManagerClient")]
+pub trait EmployeeManagerInterface {
+    fn has_permission(env: Env, employee_address: Address, required_permission: Permission) -> bool;
+}
+
+// Mirrors crm_system::CustomerTier, lowest to highest
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum CustomerTier {
+    Bronze,
+    Silver,
+    Gold,
+    Platinum,
+}
+
+// The part of crm_system that other contracts call into
+#[contractclient(name = "CrmClient")]
+pub trait CrmInterface {
+    fn get_customer_tier(env: Env, wallet_address: Address) -> CustomerTier;
+}
+
+// The part of loyalty_tokens that other contracts call into.
+// issue_points returns the ledger entry, which callers here do not decode.
+#[contractclient(name = "LoyaltyLedgerClient")]
+pub trait LoyaltyLedgerInterface {
+    fn get_balance(env: Env, owner: Address) -> i128;
+    fn issue_points(env: Env, issuer: Address, to: Address, amount: i128, reason: String, reference_id: String) -> Val;
+}
+
+// Mirrors dashboard::MetricEvent
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetricEvent {
+    Sale(i128),
+    CustomerRegistered,
+    StockChanged(String, u32),
+    Refund(i128),
+}
+
+// The part of dashboard that the reporting contracts call into
+#[contractclient(name = "DashboardClient")]
+pub trait DashboardInterface {
+    fn record_event(env: Env, source: Address, event: MetricEvent);
+}
+
 #[contracttype]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum RetailError {
@@ -32,6 +95,7 @@ pub enum RetailError {
     InvalidAmount = 4,
     DuplicateEntry = 5,
     EmployeeNotFound = 6,
+    Overflow = 7,
 }
 
 // Utility functions
@@ -51,6 +115,10 @@ impl From<RetailError> for soroban_sdk::Error {
             RetailError::InvalidAmount => soroban_sdk::Error::from_contract_error(4),
             RetailError::DuplicateEntry => soroban_sdk::Error::from_contract_error(5),
             RetailError::EmployeeNotFound => soroban_sdk::Error::from_contract_error(6),
+            RetailError::Overflow => soroban_sdk::Error::from_contract_error(7),
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,34 @@
+// Checked arithmetic for money and points. Every contract goes through these
+// instead of raw `+`/`-`/`*` so an overflow surfaces as RetailError::Overflow
+// rather than a wrapped value or a trap.
+use crate::RetailError;
+
+pub fn add(a: i128, b: i128) -> Result<i128, RetailError> {
+    a.checked_add(b).ok_or(RetailError::Overflow)
+}
+
+pub fn sub(a: i128, b: i128) -> Result<i128, RetailError> {
+    a.checked_sub(b).ok_or(RetailError::Overflow)
+}
+
+pub fn mul(a: i128, b: i128) -> Result<i128, RetailError> {
+    a.checked_mul(b).ok_or(RetailError::Overflow)
+}
+
+// a * b / denominator, e.g. basis-point fees and scaled exchange rates
+pub fn mul_div(a: i128, b: i128, denominator: i128) -> Result<i128, RetailError> {
+    mul(a, b)?.checked_div(denominator).ok_or(RetailError::Overflow)
+}
+
+pub fn add_u32(a: u32, b: u32) -> Result<u32, RetailError> {
+    a.checked_add(b).ok_or(RetailError::Overflow)
+}
+
+pub fn sub_u32(a: u32, b: u32) -> Result<u32, RetailError> {
+    a.checked_sub(b).ok_or(RetailError::Overflow)
+}
+
+// Narrows an amount into a u32 counter (points, quantities)
+pub fn to_u32(value: i128) -> Result<u32, RetailError> {
+    u32::try_from(value).map_err(|_| RetailError::Overflow)
+}
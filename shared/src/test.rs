@@ -0,0 +1,48 @@
+#![cfg(test)]
+
+use crate::math;
+use crate::RetailError;
+use proptest::prelude::*;
+
+proptest! {
+    #[test]
+    fn add_matches_checked_add(a in any::<i128>(), b in any::<i128>()) {
+        match a.checked_add(b) {
+            Some(sum) => prop_assert_eq!(math::add(a, b), Ok(sum)),
+            None => prop_assert_eq!(math::add(a, b), Err(RetailError::Overflow)),
+        }
+    }
+
+    #[test]
+    fn sub_matches_checked_sub(a in any::<i128>(), b in any::<i128>()) {
+        match a.checked_sub(b) {
+            Some(diff) => prop_assert_eq!(math::sub(a, b), Ok(diff)),
+            None => prop_assert_eq!(math::sub(a, b), Err(RetailError::Overflow)),
+        }
+    }
+
+    #[test]
+    fn mul_div_never_wraps(a in any::<i128>(), bps in 0i128..=10_000) {
+        match a.checked_mul(bps) {
+            Some(product) => prop_assert_eq!(math::mul_div(a, bps, 10_000), Ok(product / 10_000)),
+            None => prop_assert_eq!(math::mul_div(a, bps, 10_000), Err(RetailError::Overflow)),
+        }
+    }
+
+    #[test]
+    fn to_u32_rejects_out_of_range(value in any::<i128>()) {
+        let in_range = (0..=u32::MAX as i128).contains(&value);
+        prop_assert_eq!(math::to_u32(value).is_ok(), in_range);
+    }
+}
+
+#[test]
+fn test_edges() {
+    assert_eq!(math::add(i128::MAX, 1), Err(RetailError::Overflow));
+    assert_eq!(math::sub(i128::MIN, 1), Err(RetailError::Overflow));
+    assert_eq!(math::mul(i128::MAX, 2), Err(RetailError::Overflow));
+    assert_eq!(math::mul_div(1, 1, 0), Err(RetailError::Overflow));
+    assert_eq!(math::add_u32(u32::MAX, 1), Err(RetailError::Overflow));
+    assert_eq!(math::sub_u32(0, 1), Err(RetailError::Overflow));
+    assert_eq!(math::to_u32(u32::MAX as i128), Ok(u32::MAX));
+}
@@ -1,4 +1,3 @@
-// ✅ CODE CHÍNH HOÀN CHỈNH - KHÔNG CÓ TEST
 #![no_std]
 use soroban_sdk::{
     contract, contractimpl, contracttype, contracterror, 
@@ -32,6 +31,60 @@ pub struct VendorPerformance {
     pub average_rating: u32,
 }
 
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PurchaseOrderStatus {
+    Draft,
+    Submitted,
+    Acknowledged,
+    PartiallyReceived,
+    Received,
+    Cancelled,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PurchaseOrderLine {
+    pub sku: String,
+    pub quantity: u32,
+    pub unit_cost: i128, // agreed with the vendor
+    pub received_quantity: u32,
+}
+
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PurchaseOrder {
+    pub po_id: u32,
+    pub vendor_id: String,
+    pub lines: Vec<PurchaseOrderLine>,
+    pub total_cost: i128,
+    pub status: PurchaseOrderStatus,
+    pub created_at: u64,
+    pub submitted_at: u64,
+    pub acknowledged_at: u64,
+    pub updated_at: u64,
+}
+
+// Running purchase-order totals per vendor, so performance never scans the orders
+#[contracttype]
+#[derive(Clone, Debug, PartialEq)]
+pub struct VendorOrderStats {
+    pub total_orders: u32,
+    pub completed_orders: u32,
+    pub total_revenue: i128,
+}
+
+#[contracttype]
+#[derive(Clone)]
+pub enum DataKey {
+    PurchaseOrder(u32),
+    VendorOrders(String),
+    VendorOrderStats(String),
+}
+
+const BUMP_THRESHOLD: u32 = 17_280; // ~1 day
+const BUMP_AMOUNT: u32 = 518_400; // ~30 days
+
 #[contracterror]
 #[derive(Clone, Debug, Copy, PartialEq)]
 pub enum VendorError {
@@ -40,6 +93,13 @@ pub enum VendorError {
     DuplicateVendor = 3,
     InvalidRating = 4,
     VendorInactive = 5,
+    PurchaseOrderNotFound = 6,
+    InvalidOrderLine = 7,
+    ProductNotSupplied = 8,
+    InvalidOrderStatus = 9,
+    ExcessReceipt = 10,
+    DuplicateOrderLine = 11,
+    Overflow = 12,
 }
 
 #[contractimpl]
@@ -49,6 +109,7 @@ impl VendorManager {
         env.storage().instance().set(&Symbol::new(&env, "ADMIN"), &admin);
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn add_vendor(
         env: Env,
         admin: Address,
@@ -137,7 +198,7 @@ impl VendorManager {
     ) -> Result<Vendor, VendorError> {
         rater.require_auth();
 
-        if rating < 1 || rating > 5 {
+        if !(1..=5).contains(&rating) {
             return Err(VendorError::InvalidRating);
         }
 
@@ -294,11 +355,13 @@ impl VendorManager {
     pub fn get_vendor_performance(env: Env, vendor_id: String) -> Result<VendorPerformance, VendorError> {
         let vendor = Self::get_vendor(env.clone(), vendor_id.clone())?;
 
+        // Orders the vendor has been sent; revenue counts fully received orders only
+        let stats = Self::_load_order_stats(&env, &vendor_id);
         let performance = VendorPerformance {
             vendor_id,
-            total_orders: 0,
-            completed_orders: 0,
-            total_revenue: 0,
+            total_orders: stats.total_orders,
+            completed_orders: stats.completed_orders,
+            total_revenue: stats.total_revenue,
             average_rating: vendor.rating,
         };
 
@@ -358,9 +421,257 @@ impl VendorManager {
 
         vendors.contains_key(vendor_id)
     }
+
+    // Starts as a Draft; every SKU must be one the vendor supplies, on a single line
+    pub fn create_purchase_order(
+        env: Env,
+        admin: Address,
+        vendor_id: String,
+        lines: Vec<PurchaseOrderLine>,
+    ) -> Result<PurchaseOrder, VendorError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let vendor = Self::get_vendor(env.clone(), vendor_id.clone())?;
+        if !vendor.active {
+            return Err(VendorError::VendorInactive);
+        }
+
+        if lines.is_empty() {
+            return Err(VendorError::InvalidOrderLine);
+        }
+
+        let mut order_lines = Vec::new(&env);
+        let mut total_cost: i128 = 0;
+        for line in lines.iter() {
+            if line.quantity == 0 || line.unit_cost <= 0 {
+                return Err(VendorError::InvalidOrderLine);
+            }
+            if !vendor.products_supplied.contains(&line.sku) {
+                return Err(VendorError::ProductNotSupplied);
+            }
+            if order_lines.iter().any(|existing: PurchaseOrderLine| existing.sku == line.sku) {
+                return Err(VendorError::DuplicateOrderLine);
+            }
+
+            total_cost = (line.quantity as i128)
+                .checked_mul(line.unit_cost)
+                .and_then(|line_cost| total_cost.checked_add(line_cost))
+                .ok_or(VendorError::InvalidOrderLine)?;
+            order_lines.push_back(PurchaseOrderLine {
+                received_quantity: 0,
+                ..line
+            });
+        }
+
+        let counter_key = Symbol::new(&env, "PO_COUNTER");
+        let po_id: u32 = env.storage().instance().get(&counter_key).unwrap_or(0) + 1;
+        env.storage().instance().set(&counter_key, &po_id);
+
+        let now = env.ledger().timestamp();
+        let order = PurchaseOrder {
+            po_id,
+            vendor_id,
+            lines: order_lines,
+            total_cost,
+            status: PurchaseOrderStatus::Draft,
+            created_at: now,
+            submitted_at: 0,
+            acknowledged_at: 0,
+            updated_at: now,
+        };
+        Self::_save_purchase_order(&env, &order);
+
+        let index_key = DataKey::VendorOrders(order.vendor_id.clone());
+        let mut vendor_orders: Vec<u32> = env.storage().persistent().get(&index_key).unwrap_or_else(|| Vec::new(&env));
+        vendor_orders.push_back(po_id);
+        env.storage().persistent().set(&index_key, &vendor_orders);
+        env.storage().persistent().extend_ttl(&index_key, BUMP_THRESHOLD, BUMP_AMOUNT);
+
+        Ok(order)
+    }
+
+    pub fn submit_purchase_order(env: Env, admin: Address, po_id: u32) -> Result<PurchaseOrder, VendorError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let mut order = Self::get_purchase_order(env.clone(), po_id)?;
+        if order.status != PurchaseOrderStatus::Draft {
+            return Err(VendorError::InvalidOrderStatus);
+        }
+
+        let vendor = Self::get_vendor(env.clone(), order.vendor_id.clone())?;
+        if !vendor.active {
+            return Err(VendorError::VendorInactive);
+        }
+
+        let now = env.ledger().timestamp();
+        order.status = PurchaseOrderStatus::Submitted;
+        order.submitted_at = now;
+        order.updated_at = now;
+        Self::_save_purchase_order(&env, &order);
+
+        let mut stats = Self::_load_order_stats(&env, &order.vendor_id);
+        stats.total_orders = stats.total_orders.checked_add(1).ok_or(VendorError::Overflow)?;
+        Self::_save_order_stats(&env, &order.vendor_id, &stats);
+
+        Ok(order)
+    }
+
+    // Signed by the vendor's contact_address to accept the quantities and costs
+    pub fn acknowledge_purchase_order(env: Env, vendor_address: Address, po_id: u32) -> Result<PurchaseOrder, VendorError> {
+        vendor_address.require_auth();
+
+        let mut order = Self::get_purchase_order(env.clone(), po_id)?;
+        let vendor = Self::get_vendor(env.clone(), order.vendor_id.clone())?;
+        if vendor.contact_address != vendor_address {
+            return Err(VendorError::Unauthorized);
+        }
+        if order.status != PurchaseOrderStatus::Submitted {
+            return Err(VendorError::InvalidOrderStatus);
+        }
+
+        let now = env.ledger().timestamp();
+        order.status = PurchaseOrderStatus::Acknowledged;
+        order.acknowledged_at = now;
+        order.updated_at = now;
+        Self::_save_purchase_order(&env, &order);
+
+        Ok(order)
+    }
+
+    // Books a delivery against one line; the order is Received once every line is complete
+    pub fn receive_purchase_order_items(
+        env: Env,
+        admin: Address,
+        po_id: u32,
+        sku: String,
+        quantity: u32,
+    ) -> Result<PurchaseOrder, VendorError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let mut order = Self::get_purchase_order(env.clone(), po_id)?;
+        if order.status != PurchaseOrderStatus::Acknowledged && order.status != PurchaseOrderStatus::PartiallyReceived {
+            return Err(VendorError::InvalidOrderStatus);
+        }
+        if quantity == 0 {
+            return Err(VendorError::InvalidOrderLine);
+        }
+
+        let index = order
+            .lines
+            .iter()
+            .position(|line| line.sku == sku)
+            .ok_or(VendorError::ProductNotSupplied)? as u32;
+        let mut line = order.lines.get(index).unwrap();
+        let received_quantity = line
+            .received_quantity
+            .checked_add(quantity)
+            .filter(|received| *received <= line.quantity)
+            .ok_or(VendorError::ExcessReceipt)?;
+        line.received_quantity = received_quantity;
+        order.lines.set(index, line);
+
+        let complete = order.lines.iter().all(|line| line.received_quantity == line.quantity);
+        order.status = if complete {
+            PurchaseOrderStatus::Received
+        } else {
+            PurchaseOrderStatus::PartiallyReceived
+        };
+        order.updated_at = env.ledger().timestamp();
+        Self::_save_purchase_order(&env, &order);
+
+        if complete {
+            let mut stats = Self::_load_order_stats(&env, &order.vendor_id);
+            stats.completed_orders = stats.completed_orders.checked_add(1).ok_or(VendorError::Overflow)?;
+            stats.total_revenue = stats.total_revenue.checked_add(order.total_cost).ok_or(VendorError::Overflow)?;
+            Self::_save_order_stats(&env, &order.vendor_id, &stats);
+        }
+
+        Ok(order)
+    }
+
+    // Only before any goods have arrived
+    pub fn cancel_purchase_order(env: Env, admin: Address, po_id: u32) -> Result<PurchaseOrder, VendorError> {
+        admin.require_auth();
+        Self::_require_admin(&env, &admin)?;
+
+        let mut order = Self::get_purchase_order(env.clone(), po_id)?;
+        let was_sent = match order.status {
+            PurchaseOrderStatus::Draft => false,
+            PurchaseOrderStatus::Submitted | PurchaseOrderStatus::Acknowledged => true,
+            _ => return Err(VendorError::InvalidOrderStatus),
+        };
+
+        order.status = PurchaseOrderStatus::Cancelled;
+        order.updated_at = env.ledger().timestamp();
+        Self::_save_purchase_order(&env, &order);
+
+        // Cancelled orders no longer count towards the vendor's total
+        if was_sent {
+            let mut stats = Self::_load_order_stats(&env, &order.vendor_id);
+            stats.total_orders = stats.total_orders.saturating_sub(1);
+            Self::_save_order_stats(&env, &order.vendor_id, &stats);
+        }
+
+        Ok(order)
+    }
+
+    pub fn get_purchase_order(env: Env, po_id: u32) -> Result<PurchaseOrder, VendorError> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PurchaseOrder(po_id))
+            .ok_or(VendorError::PurchaseOrderNotFound)
+    }
+
+    // The vendor's orders oldest first, `limit` at a time from position `start`
+    pub fn get_vendor_purchase_orders(env: Env, vendor_id: String, start: u32, limit: u32) -> Vec<PurchaseOrder> {
+        let vendor_orders: Vec<u32> = env
+            .storage()
+            .persistent()
+            .get(&DataKey::VendorOrders(vendor_id))
+            .unwrap_or_else(|| Vec::new(&env));
+
+        let mut results = Vec::new(&env);
+        let end = vendor_orders.len().min(start.saturating_add(limit));
+        for i in start..end {
+            if let Ok(order) = Self::get_purchase_order(env.clone(), vendor_orders.get(i).unwrap()) {
+                results.push_back(order);
+            }
+        }
+        results
+    }
+
+    fn _save_purchase_order(env: &Env, order: &PurchaseOrder) {
+        let key = DataKey::PurchaseOrder(order.po_id);
+        env.storage().persistent().set(&key, order);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    fn _load_order_stats(env: &Env, vendor_id: &String) -> VendorOrderStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::VendorOrderStats(vendor_id.clone()))
+            .unwrap_or(VendorOrderStats {
+                total_orders: 0,
+                completed_orders: 0,
+                total_revenue: 0,
+            })
+    }
+
+    fn _save_order_stats(env: &Env, vendor_id: &String, stats: &VendorOrderStats) {
+        let key = DataKey::VendorOrderStats(vendor_id.clone());
+        env.storage().persistent().set(&key, stats);
+        env.storage().persistent().extend_ttl(&key, BUMP_THRESHOLD, BUMP_AMOUNT);
+    }
+
+    fn _require_admin(env: &Env, address: &Address) -> Result<(), VendorError> {
+        if !Self::is_admin(env.clone(), address.clone()) {
+            return Err(VendorError::Unauthorized);
+        }
+        Ok(())
+    }
 }
 
-// ✅ HOẶC XÓA HOÀN TOÀN TEST MODULE
-// #[cfg(test)]
-// mod test {
-//     // No tests - just build the contract
+mod test;
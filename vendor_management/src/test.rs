@@ -0,0 +1,149 @@
+#![cfg(test)]
+
+use super::*;
+use soroban_sdk::{testutils::Address as _, vec, Env};
+
+struct Setup<'a> {
+    client: VendorManagerClient<'a>,
+    admin: Address,
+    contact: Address,
+    vendor_id: String,
+}
+
+fn setup(env: &Env) -> Setup<'_> {
+    env.mock_all_auths();
+
+    let admin = Address::generate(env);
+    let contact = Address::generate(env);
+    let vendor_id = String::from_str(env, "V001");
+
+    let contract_id = env.register_contract(None, VendorManager);
+    let client = VendorManagerClient::new(env, &contract_id);
+    client.initialize(&admin);
+    client.add_vendor(
+        &admin,
+        &vendor_id,
+        &String::from_str(env, "Saigon Coffee"),
+        &contact,
+        &String::from_str(env, "0900000000"),
+        &String::from_str(env, "sales@saigoncoffee.vn"),
+        &String::from_str(env, "Beverages"),
+    );
+    client.add_vendor_product(&admin, &vendor_id, &String::from_str(env, "BEAN"));
+    client.add_vendor_product(&admin, &vendor_id, &String::from_str(env, "MILK"));
+
+    Setup {
+        client,
+        admin,
+        contact,
+        vendor_id,
+    }
+}
+
+fn line(env: &Env, sku: &str, quantity: u32, unit_cost: i128) -> PurchaseOrderLine {
+    PurchaseOrderLine {
+        sku: String::from_str(env, sku),
+        quantity,
+        unit_cost,
+        received_quantity: 0,
+    }
+}
+
+#[test]
+fn test_create_purchase_order_validation() {
+    let env = Env::default();
+    let s = setup(&env);
+    let stranger = Address::generate(&env);
+    let bean = line(&env, "BEAN", 10, 500);
+
+    assert!(s.client.try_create_purchase_order(&stranger, &s.vendor_id, &vec![&env, bean.clone()]).is_err());
+    assert!(s.client.try_create_purchase_order(&s.admin, &String::from_str(&env, "V999"), &vec![&env, bean.clone()]).is_err());
+    assert!(s.client.try_create_purchase_order(&s.admin, &s.vendor_id, &Vec::new(&env)).is_err());
+    assert!(s.client.try_create_purchase_order(&s.admin, &s.vendor_id, &vec![&env, line(&env, "BEAN", 0, 500)]).is_err());
+    assert!(s.client.try_create_purchase_order(&s.admin, &s.vendor_id, &vec![&env, line(&env, "BEAN", 10, 0)]).is_err());
+    assert!(s.client.try_create_purchase_order(&s.admin, &s.vendor_id, &vec![&env, line(&env, "TEA", 10, 500)]).is_err());
+    assert_eq!(
+        s.client.try_create_purchase_order(&s.admin, &s.vendor_id, &vec![&env, bean.clone(), line(&env, "BEAN", 5, 500)]),
+        Err(Ok(VendorError::DuplicateOrderLine))
+    );
+
+    let order = s.client.create_purchase_order(&s.admin, &s.vendor_id, &vec![&env, bean, line(&env, "MILK", 4, 250)]);
+    assert_eq!(order.po_id, 1);
+    assert_eq!(order.total_cost, 6_000);
+    assert_eq!(order.status, PurchaseOrderStatus::Draft);
+    assert_eq!(s.client.get_vendor_purchase_orders(&s.vendor_id, &0, &10).len(), 1);
+
+    s.client.set_vendor_status(&s.admin, &s.vendor_id, &false);
+    assert!(s.client.try_submit_purchase_order(&s.admin, &order.po_id).is_err());
+}
+
+#[test]
+fn test_submit_acknowledge_and_receive() {
+    let env = Env::default();
+    let s = setup(&env);
+    let lines = vec![&env, line(&env, "BEAN", 10, 500), line(&env, "MILK", 4, 250)];
+    let po_id = s.client.create_purchase_order(&s.admin, &s.vendor_id, &lines).po_id;
+    let bean = String::from_str(&env, "BEAN");
+    let milk = String::from_str(&env, "MILK");
+
+    // Nothing can be acknowledged or received before it is submitted
+    assert!(s.client.try_acknowledge_purchase_order(&s.contact, &po_id).is_err());
+    assert!(s.client.try_receive_purchase_order_items(&s.admin, &po_id, &bean, &1).is_err());
+    assert_eq!(s.client.submit_purchase_order(&s.admin, &po_id).status, PurchaseOrderStatus::Submitted);
+    assert!(s.client.try_submit_purchase_order(&s.admin, &po_id).is_err());
+
+    // Only the vendor's contact_address can acknowledge
+    assert_eq!(
+        s.client.try_acknowledge_purchase_order(&s.admin, &po_id),
+        Err(Ok(VendorError::Unauthorized))
+    );
+    assert_eq!(s.client.acknowledge_purchase_order(&s.contact, &po_id).status, PurchaseOrderStatus::Acknowledged);
+
+    let order = s.client.receive_purchase_order_items(&s.admin, &po_id, &bean, &6);
+    assert_eq!(order.status, PurchaseOrderStatus::PartiallyReceived);
+    assert_eq!(order.lines.get(0).unwrap().received_quantity, 6);
+    assert_eq!(
+        s.client.try_receive_purchase_order_items(&s.admin, &po_id, &bean, &5),
+        Err(Ok(VendorError::ExcessReceipt))
+    );
+    assert!(s.client.try_receive_purchase_order_items(&s.admin, &po_id, &String::from_str(&env, "TEA"), &1).is_err());
+    assert!(s.client.try_cancel_purchase_order(&s.admin, &po_id).is_err());
+
+    let performance = s.client.get_vendor_performance(&s.vendor_id);
+    assert_eq!(performance.total_orders, 1);
+    assert_eq!(performance.completed_orders, 0);
+
+    s.client.receive_purchase_order_items(&s.admin, &po_id, &bean, &4);
+    let order = s.client.receive_purchase_order_items(&s.admin, &po_id, &milk, &4);
+    assert_eq!(order.status, PurchaseOrderStatus::Received);
+    assert!(s.client.try_receive_purchase_order_items(&s.admin, &po_id, &milk, &1).is_err());
+
+    let performance = s.client.get_vendor_performance(&s.vendor_id);
+    assert_eq!(performance.completed_orders, 1);
+    assert_eq!(performance.total_revenue, 6_000);
+}
+
+#[test]
+fn test_cancel_purchase_order() {
+    let env = Env::default();
+    let s = setup(&env);
+    let lines = vec![&env, line(&env, "BEAN", 10, 500)];
+
+    let draft = s.client.create_purchase_order(&s.admin, &s.vendor_id, &lines).po_id;
+    assert!(s.client.try_cancel_purchase_order(&Address::generate(&env), &draft).is_err());
+    assert_eq!(s.client.cancel_purchase_order(&s.admin, &draft).status, PurchaseOrderStatus::Cancelled);
+    assert!(s.client.try_cancel_purchase_order(&s.admin, &draft).is_err());
+    assert!(s.client.try_submit_purchase_order(&s.admin, &draft).is_err());
+
+    // A cancelled order stops counting towards the vendor's total
+    let acknowledged = s.client.create_purchase_order(&s.admin, &s.vendor_id, &lines).po_id;
+    s.client.submit_purchase_order(&s.admin, &acknowledged);
+    s.client.acknowledge_purchase_order(&s.contact, &acknowledged);
+    assert_eq!(s.client.get_vendor_performance(&s.vendor_id).total_orders, 1);
+    s.client.cancel_purchase_order(&s.admin, &acknowledged);
+    assert_eq!(s.client.get_vendor_performance(&s.vendor_id).total_orders, 0);
+
+    let orders = s.client.get_vendor_purchase_orders(&s.vendor_id, &1, &10);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders.get(0).unwrap().po_id, acknowledged);
+}
@@ -0,0 +1,353 @@
+#![cfg(test)]
+
+use super::*;
+use payment_processor::{PaymentProcessor, PaymentProcessorClient};
+use proptest::prelude::*;
+use retail_events::{
+    AdminChangedEvent, ClawbackEvent, ConvertEvent, CurrencySupplyEvent, CurrencyTransferEvent, EventTopics, MintTopics,
+    PaymentCreatedEvent,
+};
+use soroban_sdk::{testutils::{Address as _, Events, Ledger}, token, Env, IntoVal, String};
+
+fn setup_token<'a>(env: &Env, admin: &Address) -> (Address, XMoneyTokenClient<'a>) {
+    let contract_id = env.register_contract(None, XMoneyToken);
+    let client = XMoneyTokenClient::new(env, &contract_id);
+    client.initialize(
+        admin,
+        &1_000_000,
+        &7,
+        &String::from_str(env, "XMoney Token"),
+        &String::from_str(env, "XMT"),
+    );
+    (contract_id, client)
+}
+
+#[test]
+fn test_sep41_allowance_and_burn() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let spender = Address::generate(&env);
+    let receiver = Address::generate(&env);
+    let (contract_id, _) = setup_token(&env, &admin);
+
+    // Drive the contract through the standard token client
+    let xmt = token::Client::new(&env, &contract_id);
+    assert_eq!(xmt.decimals(), 7);
+    assert_eq!(xmt.symbol(), String::from_str(&env, "XMT"));
+
+    xmt.approve(&admin, &spender, &500, &(env.ledger().sequence() + 100));
+    assert_eq!(xmt.allowance(&admin, &spender), 500);
+
+    xmt.transfer_from(&spender, &admin, &receiver, &300);
+    assert_eq!(xmt.balance(&receiver), 300);
+    assert_eq!(xmt.allowance(&admin, &spender), 200);
+    assert!(xmt.try_transfer_from(&spender, &admin, &receiver, &201).is_err());
+
+    xmt.burn_from(&spender, &admin, &200);
+    xmt.burn(&receiver, &100);
+    assert_eq!(xmt.allowance(&admin, &spender), 0);
+    assert_eq!(xmt.balance(&receiver), 200);
+    assert_eq!(xmt.balance(&admin), 999_500);
+}
+
+#[test]
+fn test_payment_processor_accepts_xmt() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let (xmt_id, xmt) = setup_token(&env, &admin);
+    xmt.mint(&admin, &customer, &50_000);
+
+    let processor_id = env.register_contract(None, PaymentProcessor);
+    let processor = PaymentProcessorClient::new(&env, &processor_id);
+    processor.initialize(&admin);
+
+    let record = processor.process_payment(&customer, &merchant, &20_000, &xmt_id);
+
+    assert_eq!(record.amount, 20_000);
+    assert_eq!(record.token, xmt_id);
+    assert_eq!(xmt.balance(&customer), 30_000);
+    assert_eq!(xmt.balance(&merchant), 20_000);
+}
+
+#[test]
+fn test_migrate_legacy_balances() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let other = Address::generate(&env);
+    let (contract_id, client) = setup_token(&env, &admin);
+
+    // Seed the pre-migration layout: every balance in one instance map
+    env.as_contract(&contract_id, || {
+        let mut balances: Map<Address, i128> = Map::new(&env);
+        balances.set(holder.clone(), 700);
+        balances.set(other.clone(), 300);
+        env.storage().instance().set(&Symbol::new(&env, "BALANCES"), &balances);
+    });
+    assert_eq!(client.balance(&holder), 700);
+
+    // Touching an account moves it out of the map on the spot
+    client.transfer(&holder, &admin, &200);
+    assert_eq!(client.balance(&holder), 500);
+
+    assert_eq!(client.migrate_balances(&admin, &10), 0);
+    assert_eq!(client.balance(&holder), 500);
+    assert_eq!(client.balance(&other), 300);
+    assert_eq!(client.balance(&admin), 1_000_200);
+}
+
+#[test]
+fn test_admin_controls() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let new_admin = Address::generate(&env);
+    let fraudster = Address::generate(&env);
+    let (contract_id, client) = setup_token(&env, &admin);
+
+    assert!(client
+        .try_initialize(&admin, &1, &7, &String::from_str(&env, "X"), &String::from_str(&env, "X"))
+        .is_err());
+
+    client.set_supply_cap(&admin, &1_000_500);
+    client.mint(&admin, &fraudster, &500);
+    assert!(client.try_mint(&admin, &fraudster, &1).is_err());
+
+    client.set_paused(&admin, &true);
+    assert!(client.try_transfer(&fraudster, &admin, &10).is_err());
+    client.set_paused(&admin, &false);
+
+    assert!(client.try_clawback(&admin, &fraudster, &500).is_err());
+    client.freeze_account(&admin, &fraudster);
+    assert!(client.try_transfer(&fraudster, &admin, &10).is_err());
+    client.clawback(&admin, &fraudster, &500);
+    let (emitter, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::CLAWBACK), retail_events::EVENT_VERSION, fraudster.clone());
+    assert_eq!(emitter, contract_id);
+    assert_eq!(topics, expected.into_val(&env));
+    let clawback: ClawbackEvent = data.into_val(&env);
+    assert_eq!((clawback.admin, clawback.amount), (admin.clone(), 500));
+    assert_eq!(client.balance(&fraudster), 0);
+    assert_eq!(client.get_total_supply(), 1_000_000);
+
+    client.propose_admin(&admin, &new_admin);
+    assert_eq!(client.get_admin(), admin);
+    client.accept_admin(&new_admin);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::SET_ADMIN), retail_events::EVENT_VERSION, new_admin.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let admin_changed: AdminChangedEvent = data.into_val(&env);
+    assert_eq!(admin_changed.previous_admin, admin);
+    assert_eq!(client.get_admin(), new_admin);
+    assert!(client.try_mint(&admin, &admin, &1).is_err());
+}
+
+#[test]
+fn test_pay_with_xmoney_references() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let (_, client) = setup_token(&env, &admin);
+
+    let order_a = String::from_str(&env, "ORDER_A");
+    let order_b = String::from_str(&env, "ORDER_B");
+    let first = client.pay_with_xmoney(&admin, &merchant, &1_000, &Currency::XMT, &order_a, &false);
+    let second = client.pay_with_xmoney(&admin, &merchant, &1_000, &Currency::XMT, &order_b, &false);
+
+    assert_eq!(first.transaction_hash.len(), 64);
+    assert_ne!(first.transaction_hash, second.transaction_hash);
+    assert_eq!(second.nonce, first.nonce + 1);
+    assert_eq!(client.get_payment_by_order(&order_b), second);
+
+    // Charging the same order again is rejected and moves no funds
+    assert!(client.try_pay_with_xmoney(&admin, &merchant, &1_000, &Currency::XMT, &order_a, &false).is_err());
+    assert_eq!(client.balance(&merchant), 2_000);
+}
+
+#[test]
+fn test_convert_between_currencies() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let holder = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let (_, client) = setup_token(&env, &admin);
+
+    client.mint_currency(&admin, &holder, &Currency::USDC, &1_000);
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::CURRENCY_MINT), retail_events::EVENT_VERSION, holder.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let minted: CurrencySupplyEvent = data.into_val(&env);
+    assert_eq!((minted.currency, minted.amount), (Symbol::new(&env, "USDC"), 1_000));
+    assert!(client.try_convert(&holder, &Currency::USDC, &Currency::VNDT, &100, &0).is_err());
+
+    // 1 USDC = 25,000 VNDT with a 1% spread
+    client.set_rate(&admin, &Currency::USDC, &Currency::VNDT, &(25_000 * RATE_SCALE));
+    client.set_conversion_params(&admin, &100, &3_600);
+    let amount_out = client.convert(&holder, &Currency::USDC, &Currency::VNDT, &100, &2_400_000);
+
+    let (_, topics, data) = env.events().all().last().unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::CONVERT), retail_events::EVENT_VERSION, holder.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let converted: ConvertEvent = data.into_val(&env);
+    assert_eq!(converted.from_currency, Symbol::new(&env, "USDC"));
+    assert_eq!(converted.to_currency, Symbol::new(&env, "VNDT"));
+    assert_eq!((converted.amount_in, converted.amount_out), (100, 2_475_000));
+
+    assert_eq!(amount_out, 2_475_000);
+    assert_eq!(client.balance_in(&holder, &Currency::USDC), 900);
+    assert_eq!(client.balance_in(&holder, &Currency::VNDT), 2_475_000);
+    assert_eq!(client.supply_of(&Currency::USDC), 900);
+
+    client.pay_with_xmoney(&holder, &merchant, &75_000, &Currency::VNDT, &String::from_str(&env, "ORDER_VND"), &false);
+    assert_eq!(client.balance_in(&merchant, &Currency::VNDT), 75_000);
+    assert_eq!(client.balance(&merchant), 0);
+
+    // The payment names its currency and the VNDT leg shows up as a transfer
+    let events = env.events().all();
+    let created: PaymentCreatedEvent = events.last().unwrap().2.into_val(&env);
+    assert_eq!(created.currency, Symbol::new(&env, "VNDT"));
+    let (_, topics, data) = events.get(events.len() - 2).unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::CURRENCY_TRANSFER), retail_events::EVENT_VERSION, holder.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let transferred: CurrencyTransferEvent = data.into_val(&env);
+    assert_eq!((transferred.to, transferred.amount), (merchant.clone(), 75_000));
+
+    // Converting into XMT burns the side currency and mints XMT
+    client.set_rate(&admin, &Currency::USDC, &Currency::XMT, &RATE_SCALE);
+    client.convert(&holder, &Currency::USDC, &Currency::XMT, &50, &0);
+    let events = env.events().all();
+    let (_, topics, data) = events.get(events.len() - 3).unwrap();
+    let expected: EventTopics = (Symbol::new(&env, retail_events::CURRENCY_BURN), retail_events::EVENT_VERSION, holder.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let burned: CurrencySupplyEvent = data.into_val(&env);
+    assert_eq!(burned.amount, 50);
+    let (contract_id, topics, data) = events.get(events.len() - 2).unwrap();
+    let expected: MintTopics = (Symbol::new(&env, retail_events::MINT), contract_id, holder.clone());
+    assert_eq!(topics, expected.into_val(&env));
+    let minted: i128 = data.into_val(&env);
+    assert_eq!(minted, 50);
+    assert_eq!(client.balance(&holder), 50);
+}
+
+#[test]
+fn test_pending_payment_lifecycle() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let (contract_id, client) = setup_token(&env, &admin);
+    client.mint(&admin, &customer, &10_000);
+
+    let order_a = String::from_str(&env, "ORDER_A");
+    let order_b = String::from_str(&env, "ORDER_B");
+    client.pay_with_xmoney(&customer, &merchant, &4_000, &Currency::XMT, &order_a, &true);
+    client.pay_with_xmoney(&customer, &merchant, &3_000, &Currency::XMT, &order_b, &true);
+    assert_eq!(client.balance(&contract_id), 7_000);
+    assert_eq!(client.get_payments_by_status(&PaymentStatus::Pending).len(), 2);
+
+    // Only a pending payment can be confirmed, and only a completed one refunded
+    assert!(client.try_refund_payment(&merchant, &order_a).is_err());
+    client.confirm_payment(&merchant, &order_a);
+    assert!(client.try_confirm_payment(&merchant, &order_a).is_err());
+    assert_eq!(client.balance(&merchant), 4_000);
+
+    client.fail_payment(&merchant, &order_b);
+    assert!(client.try_confirm_payment(&merchant, &order_b).is_err());
+    assert_eq!(client.balance(&customer), 6_000);
+
+    client.refund_payment(&merchant, &order_a);
+    assert_eq!(client.balance(&customer), 10_000);
+    assert_eq!(client.get_payment_by_order(&order_a).status, PaymentStatus::Refunded);
+    assert_eq!(client.get_payments_by_status(&PaymentStatus::Failed).len(), 1);
+}
+
+#[test]
+fn test_invoice_lifecycle() {
+    let env = Env::default();
+    env.mock_all_auths();
+
+    let admin = Address::generate(&env);
+    let customer = Address::generate(&env);
+    let merchant = Address::generate(&env);
+    let (_, client) = setup_token(&env, &admin);
+    client.mint(&admin, &customer, &10_000);
+
+    let memo = String::from_str(&env, "Table 4");
+    let paid = client.create_invoice(&merchant, &2_500, &Currency::XMT, &String::from_str(&env, "INV_A"), &100, &memo);
+    let stale = client.create_invoice(&merchant, &1_000, &Currency::XMT, &String::from_str(&env, "INV_B"), &50, &memo);
+    assert_eq!(client.get_open_invoices(&merchant, &0, &10).len(), 2);
+    assert_eq!(client.get_merchant_invoice_count(&merchant), 2);
+    assert_eq!(client.get_open_invoices(&merchant, &1, &10).get(0).unwrap().invoice_id, stale.invoice_id);
+
+    // An invoiced order id is reserved for that invoice only
+    let inv_a = String::from_str(&env, "INV_A");
+    assert!(client.try_create_invoice(&merchant, &500, &Currency::XMT, &inv_a, &100, &memo).is_err());
+    assert!(client.try_pay_with_xmoney(&customer, &merchant, &2_500, &Currency::XMT, &inv_a, &false).is_err());
+    let dropped = client.create_invoice(&merchant, &300, &Currency::XMT, &String::from_str(&env, "INV_C"), &100, &memo);
+    client.cancel_invoice(&merchant, &dropped.invoice_id);
+    client.pay_with_xmoney(&customer, &merchant, &300, &Currency::XMT, &String::from_str(&env, "INV_C"), &false);
+
+    let payment = client.pay_invoice(&customer, &paid.invoice_id);
+    assert_eq!(client.balance(&merchant), 2_800);
+    let invoice = client.get_invoice(&paid.invoice_id);
+    assert_eq!(invoice.status, InvoiceStatus::Paid);
+    assert_eq!(invoice.payment_reference, Some(payment.transaction_hash));
+    assert!(client.try_pay_invoice(&customer, &paid.invoice_id).is_err());
+
+    // Past expiry the invoice can no longer be paid and shows up as expired
+    env.ledger().with_mut(|li| li.timestamp = 60);
+    assert!(client.try_pay_invoice(&customer, &stale.invoice_id).is_err());
+    assert_eq!(client.get_open_invoices(&merchant, &0, &10).len(), 0);
+    assert_eq!(client.get_expired_invoices(&merchant, &0, &10).len(), 1);
+    assert_eq!(client.balance(&customer), 7_200);
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn prop_mint_never_wraps_supply(amount in 1..=i128::MAX) {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let holder = Address::generate(&env);
+        let (_, client) = setup_token(&env, &admin);
+
+        let fits = amount.checked_add(1_000_000).is_some();
+        prop_assert_eq!(client.try_mint(&admin, &holder, &amount).is_ok(), fits);
+        let expected = if fits { (1_000_000 + amount, amount) } else { (1_000_000, 0) };
+        prop_assert_eq!((client.get_total_supply(), client.balance(&holder)), expected);
+    }
+
+    #[test]
+    fn prop_transfer_never_wraps_balance(to_balance in 0..=i128::MAX, amount in 0i128..=1_000_000) {
+        let env = Env::default();
+        env.mock_all_auths();
+
+        let admin = Address::generate(&env);
+        let receiver = Address::generate(&env);
+        let (contract_id, client) = setup_token(&env, &admin);
+        env.as_contract(&contract_id, || XMoneyToken::_write_balance(&env, &receiver, to_balance));
+
+        let fits = to_balance.checked_add(amount).is_some();
+        prop_assert_eq!(client.try_transfer(&admin, &receiver, &amount).is_ok(), fits);
+        let expected = if fits { (1_000_000 - amount, to_balance + amount) } else { (1_000_000, to_balance) };
+        prop_assert_eq!((client.balance(&admin), client.balance(&receiver)), expected);
+    }
+}